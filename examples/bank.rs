@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusting_interpreters::{lox_methods, Instance, Interpreter, IntoLox, LoxClass};
+
+#[derive(LoxClass, Clone)]
+struct Account {
+    owner: String,
+    balance: f64,
+    #[lox(skip)]
+    pin: u32,
+}
+
+#[lox_methods]
+impl Account {
+    fn deposit(&mut self, amount: f64) -> f64 {
+        self.balance += amount;
+        self.balance
+    }
+
+    fn withdraw(&mut self, amount: f64) -> Result<f64, String> {
+        if amount > self.balance {
+            return Err(format!("Cannot withdraw {} from {}.", amount, self.balance));
+        }
+        self.balance -= amount;
+        Ok(self.balance)
+    }
+
+    #[lox(skip)]
+    fn check_pin(&self, pin: u32) -> bool {
+        self.pin == pin
+    }
+}
+
+fn main() {
+    let account = Rc::new(RefCell::new(Account {
+        owner: "Ada".to_string(),
+        balance: 0.0,
+        pin: 1234,
+    }));
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("account", Instance::from_rc(account.clone()).into_lox());
+
+    let script = r#"
+        print account.owner;
+        print account.deposit(10);
+        print account.withdraw(25);
+    "#;
+    if let Err(error) = interpreter.eval_str(script) {
+        eprintln!("{}", error);
+    }
+
+    let account = account.borrow();
+    println!("Host sees a balance of {}", account.balance);
+    assert!(account.check_pin(1234));
+}
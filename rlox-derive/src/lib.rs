@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, FnArg, ImplItem, ItemImpl, Pat,
+    ReturnType,
+};
+
+/// Exposes a struct with named fields as a Lox class. Every field is a
+/// readable property unless marked `#[lox(skip)]`; field types must be
+/// `Clone + IntoLox`. The type also needs a `#[lox_methods]` impl block.
+#[proc_macro_derive(LoxClass, attributes(lox))]
+pub fn derive_lox_class(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let class_name = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return syn::Error::new_spanned(name, "LoxClass needs a struct with named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(name, "LoxClass can only be derived for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let exposed: Vec<_> = fields
+        .iter()
+        .filter(|field| !is_skipped(&field.attrs))
+        .filter_map(|field| field.ident.as_ref())
+        .collect();
+    let field_names: Vec<String> = exposed.iter().map(|field| field.to_string()).collect();
+
+    quote! {
+        impl #impl_generics ::rusting_interpreters::LoxClass for #name #ty_generics #where_clause {
+            fn class_name(&self) -> &'static str {
+                #class_name
+            }
+
+            fn get_field(&self, name: &str) -> ::std::option::Option<::rusting_interpreters::LiteralValue> {
+                match name {
+                    #(#field_names => ::std::option::Option::Some(
+                        ::rusting_interpreters::IntoLox::into_lox(::std::clone::Clone::clone(&self.#exposed))
+                    ),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                &[#(#field_names),*]
+            }
+        }
+
+        impl #impl_generics ::rusting_interpreters::IntoLox for #name #ty_generics #where_clause {
+            fn into_lox(self) -> ::rusting_interpreters::LiteralValue {
+                ::rusting_interpreters::LiteralValue::Instance(
+                    ::rusting_interpreters::Instance::new(self)
+                )
+            }
+        }
+    }
+    .into()
+}
+
+/// Exposes the methods of an `impl` block to Lox. Every method taking
+/// `&self` or `&mut self` becomes callable unless marked `#[lox(skip)]`;
+/// parameters must implement `FromLox` and the return type `IntoLox` (or
+/// be a `Result<T, String>`). Associated functions and methods that take
+/// `self` by value stay Rust-only.
+#[proc_macro_attribute]
+pub fn lox_methods(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut item = parse_macro_input!(item as ItemImpl);
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+
+    let mut names = Vec::new();
+    let mut arities = Vec::new();
+    let mut calls: Vec<TokenStream2> = Vec::new();
+
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let skipped = is_skipped(&method.attrs);
+        method.attrs.retain(|attr| !attr.path().is_ident("lox"));
+        /* Methods taking `self` by value can't be called through `&mut self` */
+        let by_reference = method
+            .sig
+            .receiver()
+            .is_some_and(|receiver| receiver.reference.is_some());
+        if skipped || !by_reference {
+            continue;
+        }
+
+        let ident = &method.sig.ident;
+        let name = ident.to_string();
+        let mut conversions = Vec::new();
+        let mut arguments = Vec::new();
+        for (index, input) in method.sig.inputs.iter().skip(1).enumerate() {
+            let FnArg::Typed(typed) = input else {
+                continue;
+            };
+            let ty = &typed.ty;
+            let argument = match &*typed.pat {
+                Pat::Ident(pat) => pat.ident.clone(),
+                _ => quote::format_ident!("argument_{}", index),
+            };
+            let position = index + 1;
+            conversions.push(quote! {
+                let #argument = match <#ty as ::rusting_interpreters::FromLox>::from_lox(&arguments[#index]) {
+                    ::std::result::Result::Ok(value) => value,
+                    ::std::result::Result::Err(error) => {
+                        return ::std::option::Option::Some(::std::result::Result::Err(
+                            ::std::format!("Argument {} of '{}': {}", #position, #name, error)
+                        ))
+                    }
+                };
+            });
+            arguments.push(argument);
+        }
+
+        let result = match &method.sig.output {
+            ReturnType::Default => quote! {
+                self.#ident(#(#arguments),*);
+                ::std::result::Result::Ok(::rusting_interpreters::LiteralValue::Nil)
+            },
+            ReturnType::Type(..) => quote! {
+                ::rusting_interpreters::IntoNativeResult::into_native_result(
+                    self.#ident(#(#arguments),*)
+                )
+            },
+        };
+
+        arities.push(arguments.len());
+        calls.push(quote! {
+            #name => {
+                #(#conversions)*
+                ::std::option::Option::Some({ #result })
+            }
+        });
+        names.push(name);
+    }
+
+    quote! {
+        #item
+
+        impl #impl_generics ::rusting_interpreters::LoxMethods for #self_ty #where_clause {
+            fn method_arity(&self, name: &str) -> ::std::option::Option<usize> {
+                match name {
+                    #(#names => ::std::option::Option::Some(#arities),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn call_method(
+                &mut self,
+                name: &str,
+                arguments: &[::rusting_interpreters::LiteralValue],
+            ) -> ::std::option::Option<::rusting_interpreters::NativeResult> {
+                match name {
+                    #(#calls)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn method_names(&self) -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+        }
+    }
+    .into()
+}
+
+/** Whether a field or method carries `#[lox(skip)]` */
+fn is_skipped(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("lox")
+            && attr
+                .parse_args::<syn::Ident>()
+                .map(|ident| ident == "skip")
+                .unwrap_or(false)
+    })
+}
@@ -1,16 +1,108 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
 use std::ops::Not;
 
-use crate::environment::Environment;
+use crate::class::Instance;
+use crate::error::LoxError;
+use crate::interner::Symbol;
+use crate::interpreter::Interpreter;
+use crate::native::NativeFunction;
 use crate::scanner::{Token, TokenType};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum LiteralValue {
     Number(f64),
-    String(String),
+    String(Symbol),
     True,
     False,
     Nil,
     Expression(Box<AstNode>),
+    Native(NativeFunction),
+    List(Vec<LiteralValue>),
+    Map(HashMap<Symbol, LiteralValue>),
+    Instance(Instance),
+}
+
+impl LiteralValue {
+    /** The name of the value's type, as used in error messages */
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LiteralValue::Number(_) => "number",
+            LiteralValue::String(_) => "string",
+            LiteralValue::True | LiteralValue::False => "boolean",
+            LiteralValue::Nil => "nil",
+            LiteralValue::Expression(_) => "expression",
+            LiteralValue::Native(_) => "function",
+            LiteralValue::List(_) => "list",
+            LiteralValue::Map(_) => "map",
+            LiteralValue::Instance(_) => "instance",
+        }
+    }
+
+    /** Approximate bytes this value owns on the heap, for `Limits::max_heap_bytes` */
+    pub fn heap_size(&self) -> usize {
+        match self {
+            LiteralValue::String(str) => str.len(),
+            LiteralValue::List(values) => values
+                .iter()
+                .map(|value| std::mem::size_of::<LiteralValue>() + value.heap_size())
+                .sum(),
+            LiteralValue::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| {
+                    std::mem::size_of::<LiteralValue>() + key.len() + value.heap_size()
+                })
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /** Formats a value nested inside a list or map, quoting strings */
+    fn fmt_nested(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LiteralValue::String(str) => write!(f, "{:?}", str),
+            value => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for LiteralValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LiteralValue::Number(number) => write!(f, "{}", number),
+            LiteralValue::String(str) => write!(f, "{}", str),
+            LiteralValue::True => write!(f, "true"),
+            LiteralValue::False => write!(f, "false"),
+            LiteralValue::Nil => write!(f, "nil"),
+            LiteralValue::Expression(exp) => write!(f, "{}", exp),
+            LiteralValue::Native(native) => write!(f, "{}", native),
+            LiteralValue::Instance(instance) => write!(f, "{}", instance),
+            LiteralValue::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            LiteralValue::Map(entries) => {
+                let mut keys: Vec<&Symbol> = entries.keys().collect();
+                keys.sort_by(|a, b| str::cmp(a, b));
+                write!(f, "{{")?;
+                for (i, key) in keys.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    entries[key].fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
 }
 
 impl Not for LiteralValue {
@@ -18,8 +110,8 @@ impl Not for LiteralValue {
 
     fn not(self) -> Self::Output {
         match self {
-            LiteralValue::False | LiteralValue::Nil => LiteralValue::False,
-            _ => LiteralValue::True,
+            LiteralValue::False | LiteralValue::Nil => LiteralValue::True,
+            _ => LiteralValue::False,
         }
     }
 }
@@ -47,6 +139,16 @@ pub enum AstNode {
     Grouping {
         node: Box<AstNode>,
     },
+    Call {
+        callee: Box<AstNode>,
+        /** The closing parenthesis, used to report errors on the call's line */
+        paren: Token,
+        arguments: Vec<AstNode>,
+    },
+    Get {
+        object: Box<AstNode>,
+        name: Token,
+    },
     Literal {
         value: LiteralValue,
     },
@@ -54,7 +156,8 @@ pub enum AstNode {
         value: Box<AstNode>,
     },
     VariableExpression {
-        value: String,
+        value: Symbol,
+        line: usize,
     },
     StmtExpression {
         value: Box<AstNode>,
@@ -63,171 +166,274 @@ pub enum AstNode {
         value: Box<AstNode>,
     },
     StmtVariable {
-        name: String,
+        name: Symbol,
         initializer: Option<Box<AstNode>>,
     },
 }
 
 impl AstNode {
-    fn to_string(&self) -> String {
-        match &self {
-            AstNode::Binary {
-                left,
-                operator,
-                right,
-            } => AstNode::parenthesize(operator.lexeme.clone(), &[left, right]),
+    pub fn evaluate(self, interpreter: &mut Interpreter) -> Result<LiteralValue, LoxError> {
+        interpreter.budget.enter()?;
+        let value = self.evaluate_node(interpreter);
+        interpreter.budget.exit();
+        value
+    }
+
+    fn evaluate_node(self, interpreter: &mut Interpreter) -> Result<LiteralValue, LoxError> {
+        let value = match self {
+            node @ (AstNode::Binary { .. } | AstNode::Call { .. } | AstNode::Get { .. }) => {
+                node.evaluate_chain(interpreter)?
+            }
             AstNode::Unary { operator, right } => {
-                format!("{:?} {}", operator.to_string(), right.to_string())
+                let right = right.evaluate(interpreter)?;
+                AstNode::unary(&operator, right)
+                    .map_err(|message| LoxError::runtime(operator.line, message))?
             }
-            AstNode::Grouping { node } => node.to_string(),
-            AstNode::Literal { value } => match value {
-                LiteralValue::Number(number) => {
-                    format!("{}", number)
-                }
-                LiteralValue::String(str) => str.clone(),
-                LiteralValue::True => true.to_string(),
-                LiteralValue::False => false.to_string(),
-                LiteralValue::Nil => "nil".into(),
-                LiteralValue::Expression(exp) => exp.to_string(),
-            },
-            AstNode::Expression { value } => value.to_string(),
+            AstNode::Grouping { node } => node.evaluate(interpreter)?,
+            AstNode::Literal { value } => value,
+            AstNode::Expression { value } => value.evaluate(interpreter)?,
             AstNode::StmtPrint { value } => {
-                format!("print {}", value.to_string())
+                let to_print = value.evaluate(interpreter)?;
+                writeln!(interpreter.output, "{}", to_print)?;
+                LiteralValue::Nil
             }
-
             AstNode::StmtExpression { value } => {
-                format!("stmt expr {}", value.to_string())
+                value.evaluate(interpreter)?;
+                LiteralValue::Nil
             }
             AstNode::StmtVariable { name, initializer } => {
-                format!("var {:?} = {:?}", name, initializer)
+                let value = match initializer {
+                    Some(value) => value.evaluate(interpreter)?,
+                    None => LiteralValue::Nil,
+                };
+                interpreter.environment.define(name, value);
+                LiteralValue::Nil
+            }
+            AstNode::VariableExpression { value, line } => {
+                match interpreter.environment.get(&value) {
+                    Some(value) => value.clone(),
+                    None => {
+                        let message = format!("Undefined variable '{}'.", value);
+                        return Err(LoxError::runtime(line, &message));
+                    }
+                }
             }
-            AstNode::VariableExpression { value } => {
-                format!("var expression {:?} ", value)
+        };
+
+        Ok(value)
+    }
+
+    /// Evaluates a chain like `a + b + c`, `f()()` or `a.b.c` from its
+    /// innermost link outwards, so a long chain is a loop rather than one
+    /// level of recursion per link. Every link still counts as a step.
+    fn evaluate_chain(self, interpreter: &mut Interpreter) -> Result<LiteralValue, LoxError> {
+        let (first, links) = self.unchain();
+        let mut value = first.evaluate(interpreter)?;
+        let outermost = links.len() - 1;
+        for (i, link) in links.into_iter().enumerate() {
+            /* The outermost link was counted by `evaluate` */
+            if i < outermost {
+                interpreter.budget.step()?;
             }
+            value = link.evaluate_link(value, interpreter)?;
         }
+        Ok(value)
     }
 
-    pub fn evaluate(self, environment: &mut Environment) -> LiteralValue {
-        match self {
+    /** Evaluates one link of a chain, given the value of its left operand, callee or object */
+    fn evaluate_link(
+        self,
+        left: LiteralValue,
+        interpreter: &mut Interpreter,
+    ) -> Result<LiteralValue, LoxError> {
+        let value = match self {
             AstNode::Binary {
-                right,
-                operator,
-                left,
+                operator, right, ..
             } => {
-                let left = left.evaluate(environment);
-                let right = right.evaluate(environment);
-                match operator.ty {
-                    TokenType::Minus => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::Number(left - right)
-                        }
-                        _ => panic!("Cannot subtract non-numbers"),
-                    },
-                    TokenType::Slash => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::Number(left / right)
-                        }
-                        _ => panic!("Cannot divide non-numbers"),
-                    },
-                    TokenType::Star => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::Number(left * right)
-                        }
-                        _ => panic!("Cannot product non-numbers"),
-                    },
-                    TokenType::Plus => match (left, right) {
-                        (LiteralValue::String(left), LiteralValue::String(right)) => {
-                            LiteralValue::String(left + &*right)
-                        }
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::Number(left + right)
-                        }
-                        _ => panic!("Can only add numbers or strings"),
-                    },
-                    TokenType::Greater => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::from(left > right)
-                        }
-                        _ => panic!("Cannot compare non-numbers"),
-                    },
-                    TokenType::GreaterEqual => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::from(left >= right)
-                        }
-                        _ => panic!("Cannot compare non-numbers"),
-                    },
-                    TokenType::Less => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::from(left < right)
-                        }
-                        _ => panic!("Cannot compare non-numbers"),
-                    },
-                    TokenType::LessEqual => match (left, right) {
-                        (LiteralValue::Number(left), LiteralValue::Number(right)) => {
-                            LiteralValue::from(left <= right)
+                let right = right.evaluate(interpreter)?;
+                let value = AstNode::binary(&operator, left, right)
+                    .map_err(|message| LoxError::runtime(operator.line, message))?;
+                interpreter.budget.allocate(value.heap_size())?;
+                value
+            }
+            AstNode::Call {
+                paren, arguments, ..
+            } => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(argument.evaluate(interpreter)?);
+                }
+
+                interpreter.interrupt.check()?;
+                match left {
+                    LiteralValue::Native(native) => {
+                        if values.len() != native.arity {
+                            let message = format!(
+                                "Expected {} arguments but got {}.",
+                                native.arity,
+                                values.len()
+                            );
+                            return Err(LoxError::runtime(paren.line, &message));
                         }
-                        _ => panic!("Cannot compare non-numbers"),
-                    },
-                    TokenType::Equal => LiteralValue::from(left == right),
-                    TokenType::BangEqual => LiteralValue::from(left != right),
-                    _ => {
-                        dbg!(operator);
-                        panic!("Invalid token in binary expression");
+                        let value = native
+                            .call(&values)
+                            .map_err(|message| LoxError::runtime(paren.line, &message))?;
+                        interpreter.budget.allocate(value.heap_size())?;
+                        value
                     }
-                }
-            }
-            AstNode::Unary { operator, right } => {
-                let right = right.evaluate(environment);
-
-                match operator.ty {
-                    TokenType::Bang => !right,
-                    TokenType::Minus => match right {
-                        LiteralValue::Number(value) => LiteralValue::Number(-value),
-                        _ => panic!("Cannot negate non-numbers"),
-                    },
                     _ => {
-                        dbg!(operator);
-                        panic!("Invalid token in unary expression");
+                        return Err(LoxError::runtime(
+                            paren.line,
+                            "Can only call functions and classes.",
+                        ))
                     }
                 }
             }
-            AstNode::Grouping { node } => node.evaluate(environment),
-            AstNode::Literal { value } => value,
-            AstNode::Expression { value } => value.evaluate(environment),
-            AstNode::StmtPrint { value } => {
-                let literal_value = value.evaluate(environment);
-                let to_print = AstNode::Literal {
-                    value: literal_value,
-                };
-                println!("{}", to_print.to_string());
-                LiteralValue::Nil
-            }
-            AstNode::StmtExpression { value } => {
-                value.evaluate(environment);
-                LiteralValue::Nil
-            }
-            AstNode::StmtVariable { name, initializer } => {
-                if let Some(value) = initializer {
-                    let value = value.evaluate(environment);
-                    environment.define(name, value);
+            AstNode::Get { name, .. } => match left {
+                LiteralValue::Instance(instance) => match instance.get(&name.lexeme) {
+                    Some(value) => value,
+                    None => {
+                        let message = format!("Undefined property '{}'.", name.lexeme);
+                        return Err(LoxError::runtime(name.line, &message));
+                    }
+                },
+                _ => {
+                    return Err(LoxError::runtime(
+                        name.line,
+                        "Only instances have properties.",
+                    ))
                 }
-                LiteralValue::Nil
-            }
-            AstNode::VariableExpression { value } => environment.get(value).clone(),
+            },
+            node => unreachable!("{:?} is not a link of a chain", node),
+        };
+        Ok(value)
+    }
+
+    /// Takes out the left operand of a binary operator, the callee of a call
+    /// or the object of a property access, leaving `nil` in its place.
+    pub(crate) fn take_left(&mut self) -> Option<AstNode> {
+        match self {
+            AstNode::Binary { left, .. }
+            | AstNode::Call { callee: left, .. }
+            | AstNode::Get { object: left, .. } => Some(std::mem::replace(
+                &mut **left,
+                AstNode::Literal {
+                    value: LiteralValue::Nil,
+                },
+            )),
+            _ => None,
         }
     }
 
-    pub fn parenthesize(name: String, exprs: &[&AstNode]) -> String {
-        let mut builder = String::new();
+    /** The left operand, callee or object of a chain link, see `take_left` */
+    pub(crate) fn left(&self) -> Option<&AstNode> {
+        match self {
+            AstNode::Binary { left, .. }
+            | AstNode::Call { callee: left, .. }
+            | AstNode::Get { object: left, .. } => Some(left),
+            _ => None,
+        }
+    }
 
-        builder.push('(');
-        builder.push_str(&name);
-        for expr in exprs {
-            builder.push(' ');
-            builder.push_str(&expr.to_string());
+    /** Puts back a left operand taken out with `take_left` */
+    pub(crate) fn set_left(&mut self, node: AstNode) {
+        if let AstNode::Binary { left, .. }
+        | AstNode::Call { callee: left, .. }
+        | AstNode::Get { object: left, .. } = self
+        {
+            **left = node;
         }
-        builder.push(')');
+    }
+
+    /// Splits a chain into its innermost left operand and its links,
+    /// innermost first, each with its left operand taken out.
+    pub(crate) fn unchain(mut self) -> (AstNode, Vec<AstNode>) {
+        let mut links = Vec::new();
+        while let Some(left) = self.take_left() {
+            links.push(self);
+            self = left;
+        }
+        links.reverse();
+        (self, links)
+    }
+
+    /** Applies a binary operator to two already evaluated operands */
+    pub fn binary(
+        operator: &Token,
+        left: LiteralValue,
+        right: LiteralValue,
+    ) -> Result<LiteralValue, &'static str> {
+        let value = match operator.ty {
+            TokenType::Minus => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::Number(left - right)
+                }
+                _ => return Err("Cannot subtract non-numbers"),
+            },
+            TokenType::Slash => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::Number(left / right)
+                }
+                _ => return Err("Cannot divide non-numbers"),
+            },
+            TokenType::Star => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::Number(left * right)
+                }
+                _ => return Err("Cannot product non-numbers"),
+            },
+            TokenType::Plus => match (left, right) {
+                (LiteralValue::String(left), LiteralValue::String(right)) => {
+                    LiteralValue::String(Symbol::concat(&left, &right))
+                }
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::Number(left + right)
+                }
+                _ => return Err("Can only add numbers or strings"),
+            },
+            TokenType::Greater => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::from(left > right)
+                }
+                _ => return Err("Cannot compare non-numbers"),
+            },
+            TokenType::GreaterEqual => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::from(left >= right)
+                }
+                _ => return Err("Cannot compare non-numbers"),
+            },
+            TokenType::Less => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::from(left < right)
+                }
+                _ => return Err("Cannot compare non-numbers"),
+            },
+            TokenType::LessEqual => match (left, right) {
+                (LiteralValue::Number(left), LiteralValue::Number(right)) => {
+                    LiteralValue::from(left <= right)
+                }
+                _ => return Err("Cannot compare non-numbers"),
+            },
+            TokenType::EqualEqual => LiteralValue::from(left == right),
+            TokenType::BangEqual => LiteralValue::from(left != right),
+            _ => return Err("Invalid token in binary expression"),
+        };
+
+        Ok(value)
+    }
+
+    /** Applies a unary operator to an already evaluated operand */
+    pub fn unary(operator: &Token, right: LiteralValue) -> Result<LiteralValue, &'static str> {
+        let value = match operator.ty {
+            TokenType::Bang => !right,
+            TokenType::Minus => match right {
+                LiteralValue::Number(value) => LiteralValue::Number(-value),
+                _ => return Err("Cannot negate non-numbers"),
+            },
+            _ => return Err("Invalid token in unary expression"),
+        };
 
-        builder
+        Ok(value)
     }
 }
@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::LiteralValue;
+use crate::convert::{FromLox, IntoLox};
+use crate::native::{NativeFunction, NativeResult};
+
+/// A Rust type exposed to Lox as a class, usually through
+/// `#[derive(LoxClass)]`. Fields are readable as properties.
+pub trait LoxClass: LoxMethods + 'static {
+    fn class_name(&self) -> &'static str;
+
+    fn get_field(&self, name: &str) -> Option<LiteralValue>;
+
+    fn field_names(&self) -> &'static [&'static str];
+}
+
+/// The methods of a `LoxClass`, usually generated by `#[lox_methods]` on
+/// the type's `impl` block. Types without methods still need an empty
+/// `#[lox_methods] impl Foo {}`.
+pub trait LoxMethods {
+    fn method_arity(&self, name: &str) -> Option<usize>;
+
+    /** Returns `None` when there is no method called `name` */
+    fn call_method(&mut self, name: &str, arguments: &[LiteralValue]) -> Option<NativeResult>;
+
+    fn method_names(&self) -> &'static [&'static str];
+}
+
+/// A host object living inside a Lox value. Clones share the same object,
+/// so the host can keep a handle and see changes made by scripts.
+#[derive(Clone)]
+pub struct Instance(Rc<RefCell<dyn LoxClass>>);
+
+impl Instance {
+    pub fn new<T: LoxClass>(value: T) -> Instance {
+        Instance(Rc::new(RefCell::new(value)))
+    }
+
+    pub fn from_rc<T: LoxClass>(value: Rc<RefCell<T>>) -> Instance {
+        Instance(value)
+    }
+
+    pub fn class_name(&self) -> &'static str {
+        self.0.borrow().class_name()
+    }
+
+    /** Looks up a field, or a method bound to this instance */
+    pub fn get(&self, name: &str) -> Option<LiteralValue> {
+        let object = self.0.borrow();
+        if let Some(value) = object.get_field(name) {
+            return Some(value);
+        }
+
+        let arity = object.method_arity(name)?;
+        let class_name = object.class_name();
+        let instance = self.clone();
+        let method = name.to_string();
+        let bound = NativeFunction::new(name, arity, move |arguments| {
+            /* A method may be handed one of its own instance's bound methods and call it */
+            let mut object = instance
+                .0
+                .try_borrow_mut()
+                .map_err(|_| format!("{} instance is already in use.", class_name))?;
+            match object.call_method(&method, arguments) {
+                Some(result) => result,
+                None => Err(format!("Undefined property '{}'.", method)),
+            }
+        });
+        Some(LiteralValue::Native(bound))
+    }
+
+    /** Every field and method name, for completion and inspection */
+    pub fn property_names(&self) -> Vec<&'static str> {
+        let object = self.0.borrow();
+        let mut names = object.field_names().to_vec();
+        names.extend_from_slice(object.method_names());
+        names
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} instance>", self.class_name())
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class_name())
+    }
+}
+
+impl IntoLox for Instance {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::Instance(self)
+    }
+}
+
+impl FromLox for Instance {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Instance(instance) => Ok(instance.clone()),
+            value => Err(format!(
+                "Expected an instance but got {}.",
+                value.type_name()
+            )),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::io::{self, Read};
+
+pub const USAGE: &str = "\
+Usage: rlox [command] [options]
+
+Commands:
+  run <script> [args...]     Run a script; use - to read it from stdin
+  repl                       Start an interactive session (the default)
+  watch <script> [args...]   Run a script again every time it is saved
+  fmt [--check] <files...>   Reformat files in place; - formats stdin to stdout
+  highlight [--html] <file>  Print a script in colour, or as an HTML page
+  lint <files...>            Warn about likely mistakes; see .rloxlint below
+  lsp                        Start a language server on stdin and stdout
+  <script> [args...]         Same as run
+
+Arguments after the script are available to it as the list `args`.
+
+lint reads rule severities from a .rloxlint file in the current directory
+or one of its parents, one `rule = allow|warning|error` per line. A
+`// lint:allow(rule)` comment silences a rule on its line or the next.
+
+Options:
+  -e <source> [args...]      Run source given on the command line
+  --dump-tokens              Print the scanner's tokens instead of running
+  --dump-ast                 Print the syntax tree instead of running
+  -h, --help                 Show this message";
+
+/** Where the program to run comes from */
+#[derive(Debug, PartialEq)]
+pub enum Script {
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+impl Script {
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Script::File(path) => fs::read_to_string(path),
+            Script::Stdin => {
+                let mut source = String::new();
+                io::stdin().read_to_string(&mut source)?;
+                Ok(source)
+            }
+            Script::Inline(source) => Ok(source.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Repl,
+    Help,
+    Lsp,
+    Highlight {
+        script: Script,
+        html: bool,
+    },
+    Watch {
+        path: String,
+        args: Vec<String>,
+    },
+    Lint {
+        paths: Vec<String>,
+    },
+    Fmt {
+        paths: Vec<String>,
+        /** Only report files that would change */
+        check: bool,
+    },
+    Run {
+        script: Script,
+        /** Arguments after the script, passed on to it */
+        args: Vec<String>,
+        dump_tokens: bool,
+        dump_ast: bool,
+    },
+}
+
+/** Parses the command line, without the program name */
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut rest = args;
+    let mut dump_tokens = false;
+    let mut dump_ast = false;
+    /* The dump flags may come before `run` as well as after it */
+    while let Some(flag) = rest.first() {
+        match flag.as_str() {
+            "--dump-tokens" => dump_tokens = true,
+            "--dump-ast" => dump_ast = true,
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+    let dumping = dump_tokens || dump_ast;
+
+    match rest.first().map(String::as_str) {
+        None | Some("repl") if rest.len() <= 1 && !dumping => return Ok(Command::Repl),
+        Some("repl" | "lsp" | "highlight" | "watch" | "fmt" | "lint") if dumping => {
+            return Err("--dump-tokens and --dump-ast only apply to run".to_string())
+        }
+        Some("repl") => return Err("repl takes no arguments".to_string()),
+        Some("run") => rest = &rest[1..],
+        Some("lsp") if rest.len() == 1 => return Ok(Command::Lsp),
+        Some("lsp") => return Err("lsp takes no arguments".to_string()),
+        Some("highlight") => {
+            let html = rest[1..].iter().any(|arg| arg == "--html");
+            let mut paths = rest[1..].iter().filter(|arg| *arg != "--html");
+            return match (paths.next(), paths.next()) {
+                (Some(path), None) if path == "-" => Ok(Command::Highlight {
+                    script: Script::Stdin,
+                    html,
+                }),
+                (Some(path), None) if !path.starts_with('-') => Ok(Command::Highlight {
+                    script: Script::File(path.clone()),
+                    html,
+                }),
+                _ => Err("highlight needs the path of one script".to_string()),
+            };
+        }
+        Some("watch") => {
+            return match rest.get(1) {
+                Some(path) if !path.starts_with('-') => Ok(Command::Watch {
+                    path: path.clone(),
+                    args: rest[2..].to_vec(),
+                }),
+                _ => Err("watch needs the path of a script".to_string()),
+            }
+        }
+        Some("fmt") => {
+            let check = rest[1..].iter().any(|arg| arg == "--check");
+            let paths: Vec<String> = rest[1..]
+                .iter()
+                .filter(|arg| *arg != "--check")
+                .cloned()
+                .collect();
+            if let Some(flag) = paths
+                .iter()
+                .find(|arg| arg.len() > 1 && arg.starts_with('-'))
+            {
+                return Err(format!("Unknown option {}", flag));
+            }
+            if paths.is_empty() {
+                return Err("fmt needs at least one file".to_string());
+            }
+            return Ok(Command::Fmt { paths, check });
+        }
+        Some("lint") => {
+            let paths = rest[1..].to_vec();
+            if let Some(flag) = paths
+                .iter()
+                .find(|arg| arg.len() > 1 && arg.starts_with('-'))
+            {
+                return Err(format!("Unknown option {}", flag));
+            }
+            if paths.is_empty() {
+                return Err("lint needs at least one file".to_string());
+            }
+            return Ok(Command::Lint { paths });
+        }
+        _ => {}
+    }
+
+    while let Some(arg) = rest.first() {
+        let script = match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--dump-tokens" => {
+                dump_tokens = true;
+                rest = &rest[1..];
+                continue;
+            }
+            "--dump-ast" => {
+                dump_ast = true;
+                rest = &rest[1..];
+                continue;
+            }
+            "-e" => {
+                let source = rest.get(1).ok_or("-e needs the source to run")?;
+                rest = &rest[1..];
+                Script::Inline(source.clone())
+            }
+            "-" => Script::Stdin,
+            flag if flag.starts_with('-') => return Err(format!("Unknown option {}", flag)),
+            path => Script::File(path.to_string()),
+        };
+
+        return Ok(Command::Run {
+            script,
+            args: rest[1..].to_vec(),
+            dump_tokens,
+            dump_ast,
+        });
+    }
+
+    Err("Expected a script to run".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        super::parse(&args)
+    }
+
+    fn run(script: Script, args: &[&str]) -> Command {
+        Command::Run {
+            script,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            dump_tokens: false,
+            dump_ast: false,
+        }
+    }
+
+    #[test]
+    fn parses_each_way_to_run_a_script() {
+        assert_eq!(parse(&[]), Ok(Command::Repl));
+        assert_eq!(parse(&["repl"]), Ok(Command::Repl));
+        assert_eq!(
+            parse(&["a.lox", "x", "-e"]),
+            Ok(run(Script::File("a.lox".to_string()), &["x", "-e"]))
+        );
+        assert_eq!(
+            parse(&["run", "a.lox"]),
+            Ok(run(Script::File("a.lox".to_string()), &[]))
+        );
+        assert_eq!(parse(&["-", "x"]), Ok(run(Script::Stdin, &["x"])));
+        assert_eq!(
+            parse(&["-e", "print 1;", "x"]),
+            Ok(run(Script::Inline("print 1;".to_string()), &["x"]))
+        );
+        assert_eq!(
+            parse(&["--dump-ast", "run", "a.lox"]),
+            Ok(Command::Run {
+                script: Script::File("a.lox".to_string()),
+                args: vec![],
+                dump_tokens: false,
+                dump_ast: true,
+            })
+        );
+        assert_eq!(
+            parse(&["--dump-ast", "run", "a.lox"]),
+            parse(&["run", "--dump-ast", "a.lox"])
+        );
+        assert_eq!(parse(&["--help", "a.lox"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn parses_the_other_subcommands() {
+        assert_eq!(parse(&["lsp"]), Ok(Command::Lsp));
+        assert_eq!(
+            parse(&["watch", "a.lox", "x"]),
+            Ok(Command::Watch {
+                path: "a.lox".to_string(),
+                args: vec!["x".to_string()],
+            })
+        );
+        assert_eq!(
+            parse(&["fmt", "a.lox", "--check", "-"]),
+            Ok(Command::Fmt {
+                paths: vec!["a.lox".to_string(), "-".to_string()],
+                check: true,
+            })
+        );
+        assert_eq!(
+            parse(&["lint", "a.lox"]),
+            Ok(Command::Lint {
+                paths: vec!["a.lox".to_string()],
+            })
+        );
+        assert_eq!(
+            parse(&["highlight", "--html", "a.lox"]),
+            Ok(Command::Highlight {
+                script: Script::File("a.lox".to_string()),
+                html: true,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_command_lines() {
+        for args in [
+            &["repl", "x"][..],
+            &["lsp", "x"],
+            &["-e"],
+            &["--verbose", "a.lox"],
+            &["--dump-tokens"],
+            &["--dump-ast", "lint", "a.lox"],
+            &["watch"],
+            &["watch", "--poll"],
+            &["fmt", "--check"],
+            &["fmt", "-x", "a.lox"],
+            &["lint"],
+            &["highlight", "a.lox", "b.lox"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process::exit;
+
+use rusting_interpreters::lint::{self, Config, Severity};
+use rusting_interpreters::{formatter, highlight};
+
+use crate::cli::Script;
+
+use crate::{EX_CONFIG, EX_DATAERR, EX_IOERR};
+
+/// Formats each file in place, or with `check` only lists the files that
+/// are not formatted and exits with 1 if there are any.
+pub fn format_files(paths: &[String], check: bool) {
+    let mut unformatted = false;
+    let mut failed = None;
+    for path in paths {
+        let source = match read_path(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path, error);
+                failed = Some(EX_IOERR);
+                continue;
+            }
+        };
+
+        let formatted = match formatter::format(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}: {}", path, error);
+                }
+                failed = failed.or(Some(EX_DATAERR));
+                continue;
+            }
+        };
+
+        if check {
+            if formatted != source {
+                println!("{} is not formatted", path);
+                unformatted = true;
+            }
+        } else if path == "-" {
+            print!("{}", formatted);
+        } else if formatted != source {
+            if let Err(error) = fs::write(path, formatted) {
+                eprintln!("Could not write {}: {}", path, error);
+                failed = Some(EX_IOERR);
+            }
+        }
+    }
+
+    if let Some(code) = failed {
+        exit(code)
+    }
+    if unformatted {
+        exit(1)
+    }
+}
+
+/** Prints `script` in colour, or as an HTML page with `html` */
+pub fn highlight_script(script: &Script, html: bool) {
+    let source = match script.read() {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Could not read script: {}", error);
+            exit(EX_IOERR)
+        }
+    };
+
+    if html {
+        let title = match script {
+            Script::File(path) => path.as_str(),
+            _ => "Lox",
+        };
+        print!("{}", highlight::to_html(&source, title));
+    } else {
+        print!("{}", highlight::to_ansi(&source));
+    }
+}
+
+/// Lints each file with the rules from the nearest `.rloxlint`, exiting with
+/// 1 if any rule set to `error` is broken.
+pub fn lint_files(paths: &[String]) {
+    let config = match lint_config() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}", message);
+            exit(EX_CONFIG)
+        }
+    };
+
+    let mut broken = false;
+    let mut failed = None;
+    for path in paths {
+        let source = match read_path(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path, error);
+                failed = Some(EX_IOERR);
+                continue;
+            }
+        };
+
+        match lint::lint(&source, &config) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    println!("{}: {}", path, diagnostic);
+                    broken |= diagnostic.severity == Severity::Error;
+                }
+            }
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("{}: {}", path, error);
+                }
+                failed = failed.or(Some(EX_DATAERR));
+            }
+        }
+    }
+
+    if let Some(code) = failed {
+        exit(code)
+    }
+    if broken {
+        exit(1)
+    }
+}
+
+/** Reads the `.rloxlint` in the current directory or the closest parent that has one */
+fn lint_config() -> Result<Config, String> {
+    let current = env::current_dir().map_err(|error| error.to_string())?;
+    let Some(path) = current
+        .ancestors()
+        .map(|dir| dir.join(lint::CONFIG_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(Config::default());
+    };
+
+    let text = fs::read_to_string(&path)
+        .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+    Config::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/** Reads a file, or stdin for `-` */
+fn read_path(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).map(|_| source)
+    } else {
+        fs::read_to_string(path)
+    }
+}
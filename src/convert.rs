@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::ast::LiteralValue;
+use crate::interner::Symbol;
+
+/** Conversion from a Rust value into a Lox value */
+pub trait IntoLox {
+    fn into_lox(self) -> LiteralValue;
+}
+
+/// Conversion from a Lox value into a Rust value, failing with a message
+/// that names the expected and the actual type.
+pub trait FromLox: Sized {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String>;
+}
+
+fn type_error(expected: &str, value: &LiteralValue) -> String {
+    format!("Expected {} but got {}.", expected, value.type_name())
+}
+
+impl IntoLox for LiteralValue {
+    fn into_lox(self) -> LiteralValue {
+        self
+    }
+}
+
+impl FromLox for LiteralValue {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::Nil
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::from(self)
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::True => Ok(true),
+            LiteralValue::False => Ok(false),
+            value => Err(type_error("a boolean", value)),
+        }
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::Number(self)
+    }
+}
+
+impl FromLox for f64 {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Number(number) => Ok(*number),
+            value => Err(type_error("a number", value)),
+        }
+    }
+}
+
+impl IntoLox for f32 {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::Number(self as f64)
+    }
+}
+
+impl FromLox for f32 {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        f64::from_lox(value).map(|number| number as f32)
+    }
+}
+
+macro_rules! integer_conversions {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoLox for $ty {
+                fn into_lox(self) -> LiteralValue {
+                    LiteralValue::Number(self as f64)
+                }
+            }
+
+            impl FromLox for $ty {
+                fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+                    let number = f64::from_lox(value)?;
+                    if number.fract() != 0.0 {
+                        return Err(format!("Expected an integer but got {}.", number));
+                    }
+                    /* `MAX as f64` rounds up to `MAX + 1` for the wide types, so
+                    compare against that power of two, which is exact, instead */
+                    let end = (<$ty>::MAX / 2 + 1) as f64 * 2.0;
+                    if number < <$ty>::MIN as f64 || number >= end {
+                        return Err(format!(
+                            "{} is out of range for {}.",
+                            number,
+                            stringify!($ty)
+                        ));
+                    }
+                    Ok(number as $ty)
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl IntoLox for Symbol {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::String(self)
+    }
+}
+
+impl FromLox for Symbol {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::String(str) => Ok(str.clone()),
+            value => Err(type_error("a string", value)),
+        }
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::String(Symbol::intern(&self))
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::String(Symbol::intern(self))
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        Symbol::from_lox(value).map(|str| str.to_string())
+    }
+}
+
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> LiteralValue {
+        match self {
+            Some(value) => value.into_lox(),
+            None => LiteralValue::Nil,
+        }
+    }
+}
+
+impl<T: FromLox> FromLox for Option<T> {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Nil => Ok(None),
+            value => T::from_lox(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoLox> IntoLox for Vec<T> {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::List(self.into_iter().map(IntoLox::into_lox).collect())
+    }
+}
+
+impl<T: FromLox> FromLox for Vec<T> {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::List(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    T::from_lox(value).map_err(|error| format!("At index {}: {}", i, error))
+                })
+                .collect(),
+            value => Err(type_error("a list", value)),
+        }
+    }
+}
+
+impl<T: IntoLox, S: BuildHasher> IntoLox for HashMap<String, T, S> {
+    fn into_lox(self) -> LiteralValue {
+        LiteralValue::Map(
+            self.into_iter()
+                .map(|(key, value)| (Symbol::intern(&key), value.into_lox()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromLox, S: BuildHasher + Default> FromLox for HashMap<String, T, S> {
+    fn from_lox(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| {
+                    T::from_lox(value)
+                        .map(|value| (key.to_string(), value))
+                        .map_err(|error| format!("At key '{}': {}", key, error))
+                })
+                .collect(),
+            value => Err(type_error("a map", value)),
+        }
+    }
+}
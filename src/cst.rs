@@ -0,0 +1,329 @@
+use std::fmt;
+
+use crate::ast::{AstNode, LiteralValue};
+use crate::error::SyntaxError;
+use crate::interner::Symbol;
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Token, TokenType, TokenValue};
+
+/** Source text between tokens that the parser skips over */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /** Spaces, tabs and carriage returns */
+    Whitespace,
+    /** A single `\n` */
+    Newline,
+    /** A `//` comment, without the newline that ends it */
+    Comment,
+    /** Text the scanner reported an error for, like a stray `@` or an unterminated string */
+    Skipped,
+}
+
+/// A token together with the trivia around it.
+///
+/// Trailing trivia runs up to the end of the token's line; everything after
+/// that, including the newline, leads the next token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+    pub trailing: Vec<Trivia>,
+}
+
+impl From<Token> for SyntaxToken {
+    fn from(token: Token) -> Self {
+        SyntaxToken {
+            leading: Vec::new(),
+            token,
+            trailing: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(&trivia.text)?;
+        }
+        f.write_str(&self.token.lexeme)?;
+        for trivia in &self.trailing {
+            f.write_str(&trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /** Every statement followed by the end of file token */
+    Program,
+    VarDecl,
+    PrintStmt,
+    ExprStmt,
+    Binary,
+    Unary,
+    Grouping,
+    /** The callee, `(`, the arguments separated by `,` and `)` */
+    Call,
+    Get,
+    Literal,
+    Variable,
+    /** The tokens of a statement that did not parse */
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl From<SyntaxNode> for SyntaxElement {
+    fn from(node: SyntaxNode) -> Self {
+        SyntaxElement::Node(node)
+    }
+}
+
+impl From<SyntaxToken> for SyntaxElement {
+    fn from(token: SyntaxToken) -> Self {
+        SyntaxElement::Token(token)
+    }
+}
+
+/// A node of the concrete syntax tree. Unlike `AstNode` it keeps every
+/// token, including punctuation and trivia, so printing it gives back the
+/// source it was parsed from byte for byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn new(kind: SyntaxKind, children: Vec<SyntaxElement>) -> SyntaxNode {
+        SyntaxNode { kind, children }
+    }
+
+    /** The child nodes, skipping tokens */
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /** The tokens directly under this node, skipping child nodes */
+    pub fn tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    /// Lowers this node to the `AstNode` the interpreter runs, dropping
+    /// punctuation and trivia. Returns `None` for `Error` nodes and the
+    /// `Program` node; lower a program's statements one by one. Chains like
+    /// `a + b + c` are lowered in a loop; other nesting is bounded by
+    /// [`MAX_NESTING`](crate::parser::MAX_NESTING).
+    pub fn to_ast(&self) -> Option<AstNode> {
+        if is_link(self.kind) {
+            return self.chain_to_ast();
+        }
+
+        let mut nodes = self.nodes();
+        let mut tokens = self.tokens().map(|token| token.token.clone());
+        let mut child = || nodes.next().and_then(SyntaxNode::to_ast).map(Box::new);
+
+        let node = match self.kind {
+            SyntaxKind::Program
+            | SyntaxKind::Error
+            | SyntaxKind::Binary
+            | SyntaxKind::Call
+            | SyntaxKind::Get => return None,
+            SyntaxKind::VarDecl => AstNode::StmtVariable {
+                name: Symbol::intern(&tokens.nth(1)?.lexeme),
+                initializer: child(),
+            },
+            SyntaxKind::PrintStmt => AstNode::StmtPrint { value: child()? },
+            SyntaxKind::ExprStmt => AstNode::StmtExpression { value: child()? },
+            SyntaxKind::Unary => AstNode::Unary {
+                operator: tokens.next()?,
+                right: child()?,
+            },
+            SyntaxKind::Grouping => AstNode::Grouping { node: child()? },
+            SyntaxKind::Literal => {
+                let token = tokens.next()?;
+                let value = match (token.ty, token.literal) {
+                    (TokenType::True, _) => LiteralValue::True,
+                    (TokenType::False, _) => LiteralValue::False,
+                    (TokenType::Nil, _) => LiteralValue::Nil,
+                    (_, Some(TokenValue::NumberLiteral(value))) => LiteralValue::Number(value),
+                    (_, Some(TokenValue::StringLiteral(value))) => {
+                        LiteralValue::String(Symbol::intern(&value))
+                    }
+                    _ => return None,
+                };
+                AstNode::Literal { value }
+            }
+            SyntaxKind::Variable => {
+                let token = tokens.next()?;
+                AstNode::VariableExpression {
+                    value: Symbol::intern(&token.lexeme),
+                    line: token.line,
+                }
+            }
+        };
+        Some(node)
+    }
+
+    /** Lowers a chain like `a + b + c` or `f()()` from its innermost link outwards */
+    fn chain_to_ast(&self) -> Option<AstNode> {
+        let mut links = vec![self];
+        let mut first = self.nodes().next()?;
+        while is_link(first.kind) {
+            links.push(first);
+            first = first.nodes().next()?;
+        }
+
+        let mut node = first.to_ast()?;
+        for link in links.into_iter().rev() {
+            let left = Box::new(node);
+            let mut operands = link.nodes().skip(1);
+            let mut tokens = link.tokens().map(|token| token.token.clone());
+            node = match link.kind {
+                SyntaxKind::Binary => AstNode::Binary {
+                    left,
+                    operator: tokens.next()?,
+                    right: Box::new(operands.next()?.to_ast()?),
+                },
+                SyntaxKind::Call => AstNode::Call {
+                    callee: left,
+                    paren: tokens.last()?,
+                    arguments: operands.map(SyntaxNode::to_ast).collect::<Option<_>>()?,
+                },
+                _ => AstNode::Get {
+                    object: left,
+                    name: tokens.last()?,
+                },
+            };
+        }
+        Some(node)
+    }
+}
+
+impl Drop for SyntaxNode {
+    /** Frees the children with a stack rather than one level of recursion per link of a chain */
+    fn drop(&mut self) {
+        let mut pending: Vec<SyntaxNode> = Vec::new();
+        let mut children = std::mem::take(&mut self.children);
+        loop {
+            pending.extend(children.into_iter().filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            }));
+            match pending.pop() {
+                Some(mut node) => children = std::mem::take(&mut node.children),
+                None => break,
+            }
+        }
+    }
+}
+
+/** Whether nodes of `kind` nest through their first child, like `a + b + c` or `f()()` */
+fn is_link(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Binary | SyntaxKind::Call | SyntaxKind::Get
+    )
+}
+
+impl fmt::Display for SyntaxNode {
+    /** Writes the tokens in order, keeping a stack rather than recursing into long chains */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut pending: Vec<&SyntaxElement> = self.children.iter().rev().collect();
+        while let Some(element) = pending.pop() {
+            match element {
+                SyntaxElement::Node(node) => pending.extend(node.children.iter().rev()),
+                SyntaxElement::Token(token) => write!(f, "{}", token)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `source` into a lossless tree, along with every scanner and parser
+/// error. Statements that fail to parse become `Error` nodes, so the tree
+/// always prints back to exactly `source`.
+pub fn parse(source: &str) -> (SyntaxNode, Vec<SyntaxError>) {
+    let (tokens, mut errors) = tokens(source);
+    let (tree, parse_errors) = Parser::lossless(tokens).parse_tree();
+    errors.extend(parse_errors);
+    (tree, errors)
+}
+
+/** Scans `source` into tokens that carry the whitespace and comments around them */
+pub fn tokens(source: &str) -> (Vec<SyntaxToken>, Vec<SyntaxError>) {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+
+    let mut syntax_tokens: Vec<SyntaxToken> = Vec::with_capacity(tokens.len());
+    let mut gap_start = 0;
+    for (token, span) in tokens.into_iter().zip(scanner.spans()) {
+        let mut leading = trivia(&source[gap_start..span.start]);
+        if let Some(previous) = syntax_tokens.last_mut() {
+            let line_end = leading
+                .iter()
+                .position(|trivia| trivia.kind == TriviaKind::Newline)
+                .unwrap_or(leading.len());
+            previous.trailing = leading.drain(..line_end).collect();
+        }
+        syntax_tokens.push(SyntaxToken {
+            leading,
+            token,
+            trailing: Vec::new(),
+        });
+        gap_start = span.end;
+    }
+
+    (syntax_tokens, scanner.errors().clone())
+}
+
+/** Splits the text between two tokens into trivia */
+fn trivia(mut text: &str) -> Vec<Trivia> {
+    let mut trivia = Vec::new();
+    while let Some(first) = text.chars().next() {
+        let (kind, len) = if first == '\n' {
+            (TriviaKind::Newline, 1)
+        } else if is_space(first) {
+            let len = text.find(|c| !is_space(c)).unwrap_or(text.len());
+            (TriviaKind::Whitespace, len)
+        } else if text.starts_with("//") {
+            (TriviaKind::Comment, text.find('\n').unwrap_or(text.len()))
+        } else {
+            let len = text
+                .char_indices()
+                .skip(1)
+                .find(|&(i, c)| is_space(c) || c == '\n' || text[i..].starts_with("//"))
+                .map_or(text.len(), |(i, _)| i);
+            (TriviaKind::Skipped, len)
+        };
+
+        let (piece, rest) = text.split_at(len);
+        trivia.push(Trivia {
+            kind,
+            text: piece.to_string(),
+        });
+        text = rest;
+    }
+    trivia
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\r' | '\t')
+}
@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+
+use rusting_interpreters::parser::Parser;
+use rusting_interpreters::printer;
+use rusting_interpreters::scanner::Scanner;
+use rusting_interpreters::LoxError;
+
+/// Prints the tokens, the tree or both for `source`; returns false if it
+/// did not scan or parse, or an error if stdout could not be written.
+pub fn run(source: &str, dump_tokens: bool, dump_ast: bool) -> Result<bool, LoxError> {
+    let tokens_ok = !dump_tokens || tokens(source)?;
+    let ast_ok = !dump_ast || ast(source)?;
+    Ok(tokens_ok && ast_ok)
+}
+
+/** Prints the scanner's tokens for `source`; returns false if it reported errors */
+pub fn tokens(source: &str) -> Result<bool, LoxError> {
+    let mut scanner = Scanner::new(source.to_string());
+    let mut out = io::stdout().lock();
+    for token in scanner.scan_tokens() {
+        writeln!(out, "{:>4} {}", token.line, token)?;
+    }
+    out.flush()?;
+    for error in scanner.errors() {
+        eprintln!("{}", error);
+    }
+    Ok(scanner.errors().is_empty())
+}
+
+/// Prints the parsed tree for `source` in prefix form, either a single bare expression or
+/// a list of statements; returns false if it did not parse.
+pub fn ast(source: &str) -> Result<bool, LoxError> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+    let parsed = match Parser::new(tokens.clone()).parse_expression() {
+        Ok(expression) => Ok(vec![expression]),
+        Err(_) => Parser::new(tokens).parse(),
+    };
+
+    match parsed {
+        Ok(statements) => {
+            let mut out = io::stdout().lock();
+            for statement in statements {
+                writeln!(out, "{}", printer::lisp(&statement))?;
+            }
+            out.flush()?;
+            Ok(true)
+        }
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+            Ok(false)
+        }
+    }
+}
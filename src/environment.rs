@@ -1,24 +1,19 @@
 use std::collections::HashMap;
 
 use crate::ast::LiteralValue;
+use crate::interner::Symbol;
 
 pub struct Environment {
-    pub values: HashMap<String, LiteralValue>,
+    pub values: HashMap<Symbol, LiteralValue>,
 }
 
 impl Environment {
-    pub fn define(&mut self, name: String, value: LiteralValue) {
+    pub fn define(&mut self, name: Symbol, value: LiteralValue) {
         self.values.insert(name, value);
     }
 
-    pub fn get(&self, name: String) -> &LiteralValue {
-        match self.values.get(&name) {
-            Some(val) => val,
-            None => {
-                let error_message = format!("Undefined variable {}", name);
-                panic!("{}", error_message);
-            }
-        }
+    pub fn get(&self, name: &Symbol) -> Option<&LiteralValue> {
+        self.values.get(name)
     }
 
     pub fn new() -> Environment {
@@ -27,3 +22,9 @@ impl Environment {
         }
     }
 }
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fmt, io};
+
+use crate::limits::Limit;
+use crate::scanner::{Token, TokenType};
+
+/** A problem found by the scanner or parser before anything runs */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    /** Where on the line the error is, e.g. ` at 'foo'` or ` at end` */
+    pub location: String,
+    pub message: String,
+}
+
+impl SyntaxError {
+    pub fn new(line: usize, message: &str) -> SyntaxError {
+        SyntaxError {
+            line,
+            location: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn at(token: &Token, message: &str) -> SyntaxError {
+        let location = if token.ty == TokenType::Eof {
+            " at end".to_string()
+        } else {
+            format!(" at '{}'", token.lexeme)
+        };
+
+        SyntaxError {
+            line: token.line,
+            location,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Error{}: {}",
+            self.line, self.location, self.message
+        )
+    }
+}
+
+/** Everything that can go wrong when running Lox source */
+#[derive(Debug)]
+pub enum LoxError {
+    /** The script could not be read, or its output could not be written */
+    Io(io::Error),
+    /** The source did not scan or parse; every error found is reported */
+    Syntax(Vec<SyntaxError>),
+    /** Evaluation failed on the given line */
+    Runtime { line: usize, message: String },
+    /** Evaluation was aborted because it exceeded one of the interpreter's `Limits` */
+    LimitExceeded(Limit),
+    /** Evaluation was cancelled through an `InterruptHandle` */
+    Interrupted,
+}
+
+impl LoxError {
+    pub fn runtime(line: usize, message: &str) -> LoxError {
+        LoxError::Runtime {
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoxError::Io(error) => write!(f, "Input/output error: {}", error),
+            LoxError::Syntax(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+            LoxError::Runtime { line, message } => {
+                write!(f, "[line {}] Runtime error: {}", line, message)
+            }
+            LoxError::LimitExceeded(limit) => write!(f, "Script exceeded the {}.", limit),
+            LoxError::Interrupted => write!(f, "Script was interrupted."),
+        }
+    }
+}
+
+impl std::error::Error for LoxError {}
+
+impl From<io::Error> for LoxError {
+    fn from(error: io::Error) -> Self {
+        LoxError::Io(error)
+    }
+}
@@ -0,0 +1,326 @@
+use crate::cst::{self, SyntaxToken, TriviaKind};
+use crate::error::SyntaxError;
+use crate::scanner::{Token, TokenType};
+
+/** Lines are wrapped after a `,` or binary operator once they get longer than this */
+pub const MAX_WIDTH: usize = 80;
+const INDENT: &str = "    ";
+
+/// Reformats `source` in the canonical style: four-space indentation by
+/// brace depth, one statement per line, opening braces on the same line,
+/// single spaces around binary operators and at most one blank line in a
+/// row. Comments are kept where they were.
+///
+/// Works on the token stream rather than the syntax tree, so it handles
+/// any source that scans, even constructs the parser does not support yet
+/// like classes and `if`. It therefore does not parse: the only errors it
+/// reports are the scanner's and brackets that are not matched, and other
+/// mistakes are formatted as they are.
+pub fn format(source: &str) -> Result<String, Vec<SyntaxError>> {
+    let (tokens, mut errors) = cst::tokens(source);
+    errors.extend(unmatched_brackets(&tokens));
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut formatter = Formatter::new(comments(&tokens));
+    for token in &tokens {
+        formatter.token(&token.token);
+    }
+    Ok(formatter.out)
+}
+
+/** A `//` comment, which the parser never sees but the formatter keeps */
+struct Comment {
+    /** The comment including its leading `//` */
+    text: String,
+    line: usize,
+}
+
+/** The comments in the trivia around `tokens`, in source order */
+fn comments(tokens: &[SyntaxToken]) -> Vec<Comment> {
+    let newlines = |text: &str| text.matches('\n').count();
+    let mut comments = Vec::new();
+    for token in tokens {
+        /* A token's line is where it ends, which matters for multi-line strings */
+        let leading_newlines: usize = token
+            .leading
+            .iter()
+            .map(|trivia| newlines(&trivia.text))
+            .sum();
+        let mut line = token.token.line - newlines(&token.token.lexeme) - leading_newlines;
+        for trivia in &token.leading {
+            match trivia.kind {
+                TriviaKind::Newline => line += 1,
+                TriviaKind::Comment => comments.push(Comment {
+                    text: trivia.text.clone(),
+                    line,
+                }),
+                _ => {}
+            }
+        }
+        let trailing = token
+            .trailing
+            .iter()
+            .filter(|trivia| trivia.kind == TriviaKind::Comment);
+        comments.extend(trailing.map(|trivia| Comment {
+            text: trivia.text.clone(),
+            line: token.token.line,
+        }));
+    }
+    comments
+}
+
+struct Formatter {
+    out: String,
+    /** The line being built, without its indentation */
+    line: String,
+    line_indent: usize,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    depth: usize,
+    parens: usize,
+    /** The next token goes on a new line */
+    break_after: bool,
+    /** The next token starts a statement rather than continuing one */
+    at_statement_start: bool,
+    previous: Option<Token>,
+    previous_unary: bool,
+    /** Source line of the last token or comment written */
+    last_line: usize,
+}
+
+impl Formatter {
+    fn new(comments: Vec<Comment>) -> Formatter {
+        Formatter {
+            out: String::new(),
+            line: String::new(),
+            line_indent: 0,
+            comments,
+            next_comment: 0,
+            depth: 0,
+            parens: 0,
+            break_after: false,
+            at_statement_start: true,
+            previous: None,
+            previous_unary: false,
+            last_line: 0,
+        }
+    }
+
+    fn token(&mut self, token: &Token) {
+        if token.ty == TokenType::Eof {
+            self.comments_before(usize::MAX);
+            self.end_line();
+            return;
+        }
+        self.comments_before(token.line);
+
+        let previous = self.previous.as_ref().map(|previous| previous.ty);
+        if token.ty == TokenType::RightBrace {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        let joins_previous = matches!(
+            (previous, token.ty),
+            (Some(TokenType::LeftBrace), TokenType::RightBrace)
+                | (
+                    Some(TokenType::RightBrace),
+                    TokenType::Else | TokenType::Semicolon
+                )
+        );
+        if (self.break_after || token.ty == TokenType::RightBrace) && !joins_previous {
+            self.end_line();
+        }
+        self.break_after = false;
+
+        if self.line.is_empty() {
+            if token.ty != TokenType::RightBrace {
+                self.blank_line_before(token.line);
+            }
+            self.start_line();
+        } else if self.space_before(token) {
+            let width = self.line_indent * INDENT.len()
+                + self.line.chars().count()
+                + 1
+                + token.lexeme.chars().count();
+            if width > MAX_WIDTH && self.can_break_after() {
+                self.end_line();
+                self.start_line();
+            } else {
+                self.line.push(' ');
+            }
+        }
+        self.line.push_str(&token.lexeme);
+
+        self.previous_unary = is_unary(token, self.previous.as_ref());
+        self.at_statement_start = false;
+        match token.ty {
+            TokenType::Semicolon if self.parens == 0 => self.end_statement(),
+            TokenType::LeftBrace => {
+                self.depth += 1;
+                self.end_statement();
+            }
+            TokenType::RightBrace => self.end_statement(),
+            TokenType::LeftParen => self.parens += 1,
+            TokenType::RightParen => self.parens = self.parens.saturating_sub(1),
+            _ => {}
+        }
+        self.last_line = token.line;
+        self.previous = Some(token.clone());
+    }
+
+    /** Writes out every comment that comes before a token on `line` */
+    fn comments_before(&mut self, line: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.line >= line {
+                break;
+            }
+            let text = comment.text.trim_end().to_string();
+            let comment_line = comment.line;
+            self.next_comment += 1;
+
+            if !self.line.is_empty() && comment_line == self.last_line {
+                /* A trailing comment stays at the end of its line */
+                self.line.push(' ');
+                self.line.push_str(&text);
+            } else {
+                self.end_line();
+                self.blank_line_before(comment_line);
+                self.start_line();
+                self.line.push_str(&text);
+            }
+            self.end_line();
+            self.last_line = comment_line;
+        }
+    }
+
+    fn end_statement(&mut self) {
+        self.break_after = true;
+        self.at_statement_start = true;
+    }
+
+    /** Keeps one blank line where the source had at least one */
+    fn blank_line_before(&mut self, line: usize) {
+        if line > self.last_line + 1 && !self.out.is_empty() && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn start_line(&mut self) {
+        self.line_indent = if self.at_statement_start {
+            self.depth
+        } else {
+            self.depth + 1
+        };
+    }
+
+    fn end_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        for _ in 0..self.line_indent {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(&self.line);
+        self.out.push('\n');
+        self.line.clear();
+    }
+
+    fn space_before(&self, token: &Token) -> bool {
+        let previous = match &self.previous {
+            Some(previous) => previous,
+            None => return false,
+        };
+        if self.previous_unary {
+            return false;
+        }
+        match (previous.ty, token.ty) {
+            (
+                _,
+                TokenType::RightParen | TokenType::Comma | TokenType::Semicolon | TokenType::Dot,
+            ) => false,
+            (TokenType::LeftParen | TokenType::Dot, _) => false,
+            (TokenType::LeftBrace, TokenType::RightBrace) => false,
+            /* A call or a function's parameter list */
+            (TokenType::Ident | TokenType::RightParen, TokenType::LeftParen) => false,
+            _ => true,
+        }
+    }
+
+    /** Long lines may only be wrapped after a `,` or a binary operator */
+    fn can_break_after(&self) -> bool {
+        match &self.previous {
+            Some(previous) => {
+                previous.ty == TokenType::Comma
+                    || (is_operator(previous.ty) && !self.previous_unary)
+            }
+            None => false,
+        }
+    }
+}
+
+/** An error for each `(` or `{` that is never closed and each `)` or `}` that closes nothing */
+fn unmatched_brackets(tokens: &[SyntaxToken]) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    let mut open: Vec<&Token> = Vec::new();
+    for token in tokens.iter().map(|token| &token.token) {
+        let opener = match token.ty {
+            TokenType::LeftParen | TokenType::LeftBrace => {
+                open.push(token);
+                continue;
+            }
+            TokenType::RightParen => TokenType::LeftParen,
+            TokenType::RightBrace => TokenType::LeftBrace,
+            _ => continue,
+        };
+        if open.last().is_some_and(|last| last.ty == opener) {
+            open.pop();
+        } else {
+            errors.push(SyntaxError::at(token, "Unmatched closing bracket."));
+        }
+    }
+    for token in open {
+        errors.push(SyntaxError::at(token, "Unclosed bracket."));
+    }
+    errors
+}
+
+fn is_operator(ty: TokenType) -> bool {
+    matches!(
+        ty,
+        TokenType::Minus
+            | TokenType::Plus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::BangEqual
+            | TokenType::Equal
+            | TokenType::EqualEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::And
+            | TokenType::Or
+    )
+}
+
+/** Whether `token` is a prefix `!` or `-` rather than a binary operator */
+fn is_unary(token: &Token, previous: Option<&Token>) -> bool {
+    match token.ty {
+        TokenType::Bang => true,
+        TokenType::Minus => !matches!(
+            previous.map(|previous| previous.ty),
+            Some(
+                TokenType::Ident
+                    | TokenType::Number
+                    | TokenType::String
+                    | TokenType::RightParen
+                    | TokenType::True
+                    | TokenType::False
+                    | TokenType::Nil
+                    | TokenType::This
+            )
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+use crate::cst::{self, SyntaxToken, Trivia, TriviaKind};
+use crate::scanner::TokenType;
+
+/** What a piece of source is, which decides its colour */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Keyword,
+    String,
+    Number,
+    /** `true`, `false` and `nil` */
+    Literal,
+    Identifier,
+    /** An identifier that is called or declared with parameters */
+    Function,
+    /** An identifier after a `.` */
+    Property,
+    Operator,
+    Punctuation,
+    Comment,
+    /** Text the scanner could not make sense of */
+    Error,
+    Whitespace,
+}
+
+impl Highlight {
+    /** The CSS class `to_html` wraps this kind of text in */
+    pub fn class(self) -> &'static str {
+        match self {
+            Highlight::Keyword => "keyword",
+            Highlight::String => "string",
+            Highlight::Number => "number",
+            Highlight::Literal => "literal",
+            Highlight::Identifier => "identifier",
+            Highlight::Function => "function",
+            Highlight::Property => "property",
+            Highlight::Operator => "operator",
+            Highlight::Punctuation => "punctuation",
+            Highlight::Comment => "comment",
+            Highlight::Error => "error",
+            Highlight::Whitespace => "whitespace",
+        }
+    }
+
+    /** The SGR escape `to_ansi` starts this kind of text with, if it is coloured */
+    fn ansi(self) -> Option<&'static str> {
+        match self {
+            Highlight::Keyword => Some("\x1b[35m"),
+            Highlight::String => Some("\x1b[32m"),
+            Highlight::Number | Highlight::Literal => Some("\x1b[33m"),
+            Highlight::Function => Some("\x1b[34m"),
+            Highlight::Property => Some("\x1b[36m"),
+            Highlight::Comment => Some("\x1b[90m"),
+            Highlight::Error => Some("\x1b[31;4m"),
+            _ => None,
+        }
+    }
+}
+
+/** Styles for the classes used by `to_html` */
+pub const STYLESHEET: &str = "\
+pre.lox { background: #fafafa; color: #24292e; padding: 1em; }
+.lox .keyword { color: #a626a4; font-weight: bold; }
+.lox .string { color: #50a14f; }
+.lox .number, .lox .literal { color: #986801; }
+.lox .function { color: #4078f2; }
+.lox .property { color: #0184bc; }
+.lox .comment { color: #a0a1a7; font-style: italic; }
+.lox .error { color: #e45649; text-decoration: underline wavy; }
+";
+
+/// Splits `source` into pieces that cover all of it, in order, each tagged
+/// with how to colour it. Comments and text the scanner rejected are kept.
+pub fn highlight(source: &str) -> Vec<(Highlight, String)> {
+    let (tokens, _) = cst::tokens(source);
+    let mut pieces = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        trivia(&mut pieces, &token.leading);
+        if token.token.ty != TokenType::Eof {
+            let previous = i.checked_sub(1).map(|i| &tokens[i]);
+            let kind = classify(token, previous, tokens.get(i + 1));
+            pieces.push((kind, token.token.lexeme.clone()));
+        }
+        trivia(&mut pieces, &token.trailing);
+    }
+    pieces
+}
+
+/** Renders `source` with ANSI colours for a terminal */
+pub fn to_ansi(source: &str) -> String {
+    let mut out = String::new();
+    for (kind, text) in highlight(source) {
+        match kind.ansi() {
+            Some(colour) => {
+                let _ = write!(out, "{}{}\x1b[0m", colour, text);
+            }
+            None => out.push_str(&text),
+        }
+    }
+    out
+}
+
+/// Renders `source` as a `<pre class="lox">` block with a `<span>` per
+/// token, to embed in a page that includes `STYLESHEET`.
+pub fn to_html_fragment(source: &str) -> String {
+    let mut out = String::from("<pre class=\"lox\"><code>");
+    for (kind, text) in highlight(source) {
+        if kind == Highlight::Whitespace {
+            escape(&mut out, &text);
+        } else {
+            let _ = write!(out, "<span class=\"{}\">", kind.class());
+            escape(&mut out, &text);
+            out.push_str("</span>");
+        }
+    }
+    out.push_str("</code></pre>");
+    out
+}
+
+/** Renders `source` as a standalone HTML page with its stylesheet inlined */
+pub fn to_html(source: &str, title: &str) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>");
+    escape(&mut out, title);
+    out.push_str("</title>\n<style>\n");
+    out.push_str(STYLESHEET);
+    out.push_str("</style>\n</head>\n<body>\n");
+    out.push_str(&to_html_fragment(source));
+    out.push_str("\n</body>\n</html>\n");
+    out
+}
+
+fn classify(
+    token: &SyntaxToken,
+    previous: Option<&SyntaxToken>,
+    next: Option<&SyntaxToken>,
+) -> Highlight {
+    let is = |token: Option<&SyntaxToken>, ty| token.is_some_and(|token| token.token.ty == ty);
+    match token.token.ty {
+        TokenType::And
+        | TokenType::Class
+        | TokenType::Else
+        | TokenType::Fun
+        | TokenType::For
+        | TokenType::If
+        | TokenType::Or
+        | TokenType::Print
+        | TokenType::Return
+        | TokenType::Super
+        | TokenType::This
+        | TokenType::Var
+        | TokenType::While => Highlight::Keyword,
+        TokenType::True | TokenType::False | TokenType::Nil => Highlight::Literal,
+        TokenType::String => Highlight::String,
+        TokenType::Number => Highlight::Number,
+        TokenType::Ident if is(next, TokenType::LeftParen) => Highlight::Function,
+        TokenType::Ident if is(previous, TokenType::Dot) => Highlight::Property,
+        TokenType::Ident => Highlight::Identifier,
+        TokenType::Minus
+        | TokenType::Plus
+        | TokenType::Slash
+        | TokenType::Star
+        | TokenType::Bang
+        | TokenType::BangEqual
+        | TokenType::Equal
+        | TokenType::EqualEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::Less
+        | TokenType::LessEqual => Highlight::Operator,
+        TokenType::LeftParen
+        | TokenType::RightParen
+        | TokenType::LeftBrace
+        | TokenType::RightBrace
+        | TokenType::Comma
+        | TokenType::Dot
+        | TokenType::Semicolon
+        | TokenType::Eof => Highlight::Punctuation,
+    }
+}
+
+fn trivia(pieces: &mut Vec<(Highlight, String)>, trivia: &[Trivia]) {
+    for trivia in trivia {
+        let kind = match trivia.kind {
+            TriviaKind::Whitespace | TriviaKind::Newline => Highlight::Whitespace,
+            TriviaKind::Comment => Highlight::Comment,
+            TriviaKind::Skipped => Highlight::Error,
+        };
+        pieces.push((kind, trivia.text.clone()));
+    }
+}
+
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+thread_local! {
+    static STRINGS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// An interned string.
+///
+/// Every `Symbol` with the same contents shares one allocation, so equality
+/// and hashing only look at the pointer instead of the characters. A string
+/// leaves the table when its last `Symbol` is dropped.
+///
+/// The table is per thread and symbols are reference counted with `Rc`, so
+/// a `Symbol`, and any value or interpreter holding one, is not `Send`.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(value: &str) -> Symbol {
+        STRINGS.with(|strings| {
+            let mut strings = strings.borrow_mut();
+            if let Some(existing) = strings.get(value) {
+                return Symbol(existing.clone());
+            }
+
+            let symbol: Rc<str> = Rc::from(value);
+            strings.insert(symbol.clone());
+            Symbol(symbol)
+        })
+    }
+
+    /** Interns `left + right`, only allocating when the result is new */
+    pub fn concat(left: &Symbol, right: &Symbol) -> Symbol {
+        SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            scratch.clear();
+            scratch.push_str(left);
+            scratch.push_str(right);
+            Symbol::intern(&scratch)
+        })
+    }
+}
+
+impl Drop for Symbol {
+    fn drop(&mut self) {
+        /* One reference is the table's, so this is the last symbol for the string */
+        if Rc::strong_count(&self.0) == 2 {
+            let _ = STRINGS.try_with(|strings| {
+                if let Ok(mut strings) = strings.try_borrow_mut() {
+                    strings.remove(&*self.0);
+                }
+            });
+        }
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const u8 as usize).hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", &*self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interned(value: &str) -> bool {
+        STRINGS.with(|strings| strings.borrow().contains(value))
+    }
+
+    #[test]
+    fn equal_strings_share_one_symbol() {
+        let left = Symbol::intern("shared");
+        let right = Symbol::concat(&Symbol::intern("sha"), &Symbol::intern("red"));
+        assert_eq!(left, right);
+        assert!(Rc::ptr_eq(&left.0, &right.0));
+        assert_ne!(left, Symbol::intern("other"));
+    }
+
+    #[test]
+    fn strings_are_freed_with_their_last_symbol() {
+        let first = Symbol::intern("temporary");
+        let second = first.clone();
+        drop(first);
+        assert!(interned("temporary"));
+        drop(second);
+        assert!(!interned("temporary"));
+
+        let built = Symbol::concat(&Symbol::intern("a"), &Symbol::intern("b"));
+        assert!(interned("ab"));
+        drop(built);
+        assert!(!interned("ab"));
+    }
+}
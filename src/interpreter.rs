@@ -0,0 +1,222 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::ast::LiteralValue;
+use crate::environment::Environment;
+use crate::error::LoxError;
+use crate::interner::Symbol;
+use crate::interrupt::InterruptHandle;
+use crate::limits::{Budget, Limits};
+use crate::native::{NativeFunction, NativeResult, TypedNative};
+use crate::optimizer;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stdlib::{self, Capability};
+
+/// A Lox session: globals persist across calls to `eval_str` and `eval_file`.
+///
+/// An interpreter is not `Send`, since its strings are interned per thread.
+/// Create and run it on one thread; other threads can stop it through an
+/// `InterruptHandle`.
+pub struct Interpreter {
+    pub(crate) environment: Environment,
+    pub(crate) budget: Budget,
+    /** Where `print` writes to */
+    pub(crate) output: Box<dyn Write>,
+    pub(crate) interrupt: InterruptHandle,
+}
+
+impl Interpreter {
+    /** An interpreter that prints to stdout and has no natives defined */
+    pub fn new() -> Interpreter {
+        Interpreter {
+            environment: Environment::new(),
+            budget: Budget::default(),
+            output: Box::new(io::stdout()),
+            interrupt: InterruptHandle::default(),
+        }
+    }
+
+    /** Starts a sandboxed interpreter with no capabilities granted */
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /** Scans, parses and runs `source` against this interpreter's globals */
+    pub fn eval_str(&mut self, source: &str) -> Result<(), LoxError> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens().clone();
+        let mut errors = scanner.errors().clone();
+
+        let mut parser = Parser::new(tokens);
+        let statements = match parser.parse() {
+            Ok(statements) => statements,
+            Err(parse_errors) => {
+                errors.extend(parse_errors);
+                Vec::new()
+            }
+        };
+        if !errors.is_empty() {
+            return Err(LoxError::Syntax(errors));
+        }
+
+        self.budget.start();
+        self.interrupt.reset();
+        let result = optimizer::optimize(statements)
+            .into_iter()
+            .try_for_each(|statement| {
+                self.interrupt.check()?;
+                statement.evaluate(self).map(drop)
+            });
+        self.flush_after(result)
+    }
+
+    /// Evaluates `source` as a single expression, without a trailing `;`,
+    /// and returns its value. Used by the REPL to echo bare expressions.
+    pub fn eval_expr(&mut self, source: &str) -> Result<LiteralValue, LoxError> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens().clone();
+        if !scanner.errors().is_empty() {
+            return Err(LoxError::Syntax(scanner.errors().clone()));
+        }
+
+        let mut parser = Parser::new(tokens);
+        let expression = parser
+            .parse_expression()
+            .map_err(|error| LoxError::Syntax(vec![error]))?;
+
+        self.budget.start();
+        self.interrupt.reset();
+        let value = optimizer::fold(expression).evaluate(self);
+        self.flush_after(value)
+    }
+
+    /// Flushes the output whether or not evaluation failed, so a host sees
+    /// what a script printed before its error. The evaluation error wins
+    /// over a failure to flush.
+    fn flush_after<T>(&mut self, result: Result<T, LoxError>) -> Result<T, LoxError> {
+        let flushed = self.output.flush();
+        let value = result?;
+        flushed?;
+        Ok(value)
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoxError> {
+        let source = fs::read_to_string(path)?;
+        self.eval_str(&source)
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<LiteralValue> {
+        self.environment.get(&Symbol::intern(name)).cloned()
+    }
+
+    pub fn set_global(&mut self, name: &str, value: LiteralValue) {
+        self.environment.define(Symbol::intern(name), value);
+    }
+
+    /** Redirects everything the script prints from now on */
+    pub fn set_output<W: Write + 'static>(&mut self, output: W) {
+        self.output = Box::new(output);
+    }
+
+    /** A handle that can cancel this interpreter's evaluation from any thread */
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    pub fn limits(&self) -> &Limits {
+        self.budget.limits()
+    }
+
+    /** Limits apply to each later call to `eval_str` or `eval_file` */
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.budget.set_limits(limits);
+    }
+
+    /** Registers a Rust closure as a global function callable from Lox */
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[LiteralValue]) -> NativeResult + 'static,
+    {
+        let native = NativeFunction::new(name, arity, function);
+        self.set_global(name, LiteralValue::Native(native));
+    }
+
+    /// Registers a Rust closure with typed parameters, e.g.
+    /// `|a: f64, b: f64| a + b`. Arguments are converted with `FromLox`
+    /// and the return value with `IntoLox`.
+    pub fn define_function<Args, F>(&mut self, name: &str, function: F)
+    where
+        F: TypedNative<Args>,
+    {
+        let native = function.into_native(name);
+        self.set_global(name, LiteralValue::Native(native));
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+/// Builds an `Interpreter` whose globals start empty and only contain the
+/// natives of the capabilities that were granted.
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    capabilities: Vec<Capability>,
+    output: Option<Box<dyn Write>>,
+    limits: Limits,
+}
+
+impl InterpreterBuilder {
+    pub fn capability(mut self, capability: Capability) -> Self {
+        if !self.capabilities.contains(&capability) {
+            self.capabilities.push(capability);
+        }
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: &[Capability]) -> Self {
+        for &capability in capabilities {
+            self = self.capability(capability);
+        }
+        self
+    }
+
+    /** Where `print` writes to when `Capability::Print` is granted; stdout by default */
+    pub fn output<W: Write + 'static>(mut self, output: W) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let output: Box<dyn Write> = if self.capabilities.contains(&Capability::Print) {
+            self.output.unwrap_or_else(|| Box::new(io::stdout()))
+        } else {
+            Box::new(io::sink())
+        };
+
+        let mut interpreter = Interpreter {
+            environment: Environment::new(),
+            budget: Budget::default(),
+            output,
+            interrupt: InterruptHandle::default(),
+        };
+        interpreter.set_limits(self.limits);
+        for capability in self.capabilities {
+            stdlib::install(&mut interpreter, capability);
+        }
+        interpreter
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::error::LoxError;
+
+/// Cancels a running evaluation from another thread.
+///
+/// The evaluator polls the flag before every top-level statement and every
+/// call. Once it sees the request it unwinds with `LoxError::Interrupted`.
+/// The flag is cleared when the next evaluation starts, so an interrupt
+/// sent while the interpreter is idle does not stop the following script.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /** Forgets any interrupt requested before the current evaluation */
+    pub(crate) fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub(crate) fn check(&self) -> Result<(), LoxError> {
+        if self.0.load(Ordering::Relaxed) {
+            return Err(LoxError::Interrupted);
+        }
+        Ok(())
+    }
+}
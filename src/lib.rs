@@ -0,0 +1,33 @@
+pub mod ast;
+pub mod class;
+pub mod convert;
+pub mod cst;
+pub mod environment;
+pub mod error;
+pub mod formatter;
+pub mod highlight;
+pub mod interner;
+pub mod interpreter;
+pub mod interrupt;
+pub mod limits;
+pub mod lint;
+pub mod native;
+pub mod optimizer;
+pub mod output;
+pub mod parser;
+pub mod printer;
+pub mod scanner;
+pub mod stdlib;
+
+pub use ast::LiteralValue;
+pub use class::{Instance, LoxClass, LoxMethods};
+pub use convert::{FromLox, IntoLox};
+pub use error::LoxError;
+pub use interner::Symbol;
+pub use interpreter::{Interpreter, InterpreterBuilder};
+pub use interrupt::InterruptHandle;
+pub use limits::{Limit, Limits};
+pub use native::{IntoNativeResult, NativeFunction, NativeResult, TypedNative};
+pub use output::{LineSink, SharedBuffer};
+pub use rlox_derive::{lox_methods, LoxClass};
+pub use stdlib::Capability;
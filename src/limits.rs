@@ -0,0 +1,108 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::error::LoxError;
+
+/// Resource limits applied to each call to `Interpreter::eval_str`.
+/// `None` means unlimited, which is the default.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /** How many AST nodes may be evaluated */
+    pub max_steps: Option<u64>,
+    /** How deeply evaluation may nest, counting calls and sub-expressions */
+    pub max_call_depth: Option<usize>,
+    /** Approximate bytes of strings and containers the script may create */
+    pub max_heap_bytes: Option<usize>,
+    /** Wall-clock time allowed before evaluation is aborted */
+    pub timeout: Option<Duration>,
+}
+
+/** Which limit stopped a script */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps,
+    CallDepth,
+    HeapBytes,
+    Timeout,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Limit::Steps => "step limit",
+            Limit::CallDepth => "call depth limit",
+            Limit::HeapBytes => "heap limit",
+            Limit::Timeout => "timeout",
+        };
+        f.write_str(name)
+    }
+}
+
+/** How many steps pass between two checks of the clock */
+const CLOCK_INTERVAL: u64 = 256;
+
+/** Usage counted against `Limits` during one evaluation */
+#[derive(Default)]
+pub(crate) struct Budget {
+    limits: Limits,
+    steps: u64,
+    depth: usize,
+    heap_bytes: usize,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub(crate) fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub(crate) fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /** Resets the counters before a new evaluation */
+    pub(crate) fn start(&mut self) {
+        self.steps = 0;
+        self.depth = 0;
+        self.heap_bytes = 0;
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /** Accounts for one evaluation step, one level deeper than the caller */
+    pub(crate) fn enter(&mut self) -> Result<(), LoxError> {
+        self.steps += 1;
+        self.depth += 1;
+
+        if matches!(self.limits.max_steps, Some(max) if self.steps > max) {
+            return Err(LoxError::LimitExceeded(Limit::Steps));
+        }
+        if matches!(self.limits.max_call_depth, Some(max) if self.depth > max) {
+            return Err(LoxError::LimitExceeded(Limit::CallDepth));
+        }
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= deadline {
+                return Err(LoxError::LimitExceeded(Limit::Timeout));
+            }
+        }
+        Ok(())
+    }
+
+    /** Accounts for one evaluation step at the caller's depth */
+    pub(crate) fn step(&mut self) -> Result<(), LoxError> {
+        self.enter()?;
+        self.exit();
+        Ok(())
+    }
+
+    pub(crate) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(crate) fn allocate(&mut self, bytes: usize) -> Result<(), LoxError> {
+        self.heap_bytes += bytes;
+        if matches!(self.limits.max_heap_bytes, Some(max) if self.heap_bytes > max) {
+            return Err(LoxError::LimitExceeded(Limit::HeapBytes));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::cst::{self, SyntaxKind, SyntaxNode, TriviaKind};
+use crate::error::SyntaxError;
+use crate::scanner::TokenType;
+use crate::stdlib;
+
+/** The project file `rlox lint` reads rule severities from */
+pub const CONFIG_FILE: &str = ".rloxlint";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /** A variable that is declared but never read */
+    UnusedVariable,
+    /** A variable read before it is declared or given a value */
+    ReadBeforeAssignment,
+    /** A declaration that hides a built-in global like `clock` */
+    Shadowing,
+    /** A comparison of the result of another comparison, like `x == nil == false` */
+    ChainedComparison,
+    /** `var a = a;` for a variable that already exists */
+    SelfAssignment,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::UnusedVariable,
+        Rule::ReadBeforeAssignment,
+        Rule::Shadowing,
+        Rule::ChainedComparison,
+        Rule::SelfAssignment,
+    ];
+
+    /** The name used in the config file and in `lint:allow` comments */
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::ReadBeforeAssignment => "read-before-assignment",
+            Rule::Shadowing => "shadowing",
+            Rule::ChainedComparison => "chained-comparison",
+            Rule::SelfAssignment => "self-assignment",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /** The rule is turned off */
+    Allow,
+    Warning,
+    /** `rlox lint` fails if the rule is broken */
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Allow => "allow",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Severity> {
+        [Severity::Allow, Severity::Warning, Severity::Error]
+            .into_iter()
+            .find(|severity| severity.name() == name)
+    }
+}
+
+/// How severe each rule is. Every rule is a warning unless the project's
+/// config file says otherwise.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Config {
+    severities: HashMap<Rule, Severity>,
+}
+
+impl Config {
+    /// Parses a config file with one `rule = severity` per line, where the
+    /// severity is `allow`, `warning` or `error`. `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (rule, severity) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `rule = severity`", i + 1))?;
+            let rule = Rule::from_name(rule.trim())
+                .ok_or_else(|| format!("line {}: unknown rule `{}`", i + 1, rule.trim()))?;
+            let severity = Severity::from_name(severity.trim())
+                .ok_or_else(|| format!("line {}: unknown severity `{}`", i + 1, severity.trim()))?;
+            config.set(rule, severity);
+        }
+        Ok(config)
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.severities
+            .get(&rule)
+            .copied()
+            .unwrap_or(Severity::Warning)
+    }
+
+    pub fn set(&mut self, rule: Rule, severity: Severity) {
+        self.severities.insert(rule, severity);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] {}: {} ({})",
+            self.line,
+            self.severity.name(),
+            self.message,
+            self.rule.name()
+        )
+    }
+}
+
+/// Checks `source` for likely mistakes, ordered by line. Rules `config`
+/// allows are skipped, and so are rules named in a `// lint:allow(rule)`
+/// comment at the end of the line or on its own line just above it.
+pub fn lint(source: &str, config: &Config) -> Result<Vec<Diagnostic>, Vec<SyntaxError>> {
+    let (tree, errors) = cst::parse(source);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut linter = Linter::new(&tree);
+    for statement in tree.nodes() {
+        linter.statement(statement);
+    }
+    linter.finish();
+
+    let allowed = allowed(source);
+    let mut diagnostics: Vec<Diagnostic> = linter
+        .found
+        .into_iter()
+        .filter(|(rule, line, _)| !allowed.contains(&(*line, *rule)))
+        .map(|(rule, line, message)| Diagnostic {
+            rule,
+            severity: config.severity(rule),
+            line,
+            message,
+        })
+        .filter(|diagnostic| diagnostic.severity != Severity::Allow)
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    Ok(diagnostics)
+}
+
+struct Declaration {
+    name: String,
+    line: usize,
+    initialized: bool,
+    read: bool,
+}
+
+struct Linter {
+    /** Globals defined before the script runs */
+    builtins: HashSet<String>,
+    /** Every name the program declares, to tell reads before a declaration from unknown names */
+    declared: HashSet<String>,
+    declarations: Vec<Declaration>,
+    /** The index in `declarations` each name currently refers to */
+    bindings: HashMap<String, usize>,
+    found: Vec<(Rule, usize, String)>,
+}
+
+impl Linter {
+    fn new(program: &SyntaxNode) -> Linter {
+        let mut builtins: HashSet<String> = stdlib::native_names()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        /* Defined by `rlox` when it runs a script */
+        builtins.insert("args".to_string());
+
+        let declared = program
+            .nodes()
+            .filter(|statement| statement.kind == SyntaxKind::VarDecl)
+            .filter_map(|statement| statement.tokens().nth(1))
+            .map(|name| name.token.lexeme.clone())
+            .collect();
+
+        Linter {
+            builtins,
+            declared,
+            declarations: Vec::new(),
+            bindings: HashMap::new(),
+            found: Vec::new(),
+        }
+    }
+
+    fn statement(&mut self, statement: &SyntaxNode) {
+        if statement.kind != SyntaxKind::VarDecl {
+            for expression in statement.nodes() {
+                self.expression(expression);
+            }
+            return;
+        }
+
+        let Some(name) = statement.tokens().nth(1) else {
+            return;
+        };
+        let (name, line) = (name.token.lexeme.clone(), name.token.line);
+        let initializer = statement.nodes().next();
+        if let Some(initializer) = initializer {
+            if variable(initializer) == Some(&name) && self.bindings.contains_key(&name) {
+                let message = format!("`{}` is assigned to itself", name);
+                self.found.push((Rule::SelfAssignment, line, message));
+            }
+            self.expression(initializer);
+        }
+        if self.builtins.contains(&name) {
+            let message = format!("`{}` shadows the built-in global of the same name", name);
+            self.found.push((Rule::Shadowing, line, message));
+        }
+
+        self.bindings.insert(name.clone(), self.declarations.len());
+        self.declarations.push(Declaration {
+            name,
+            line,
+            initialized: initializer.is_some(),
+            read: false,
+        });
+    }
+
+    fn expression(&mut self, expression: &SyntaxNode) {
+        /* Chains nest as deep as they are long, so this keeps its own stack */
+        let mut pending = vec![expression];
+        while let Some(expression) = pending.pop() {
+            match expression.kind {
+                SyntaxKind::Variable => {
+                    if let Some(token) = expression.tokens().next() {
+                        self.read(&token.token.lexeme, token.token.line);
+                    }
+                    continue;
+                }
+                SyntaxKind::Binary if is_comparison(expression) => {
+                    if let Some(inner) = expression.nodes().find(|node| is_comparison(node)) {
+                        let line = expression.tokens().next().map_or(0, |op| op.token.line);
+                        let message = format!(
+                            "`{}` compares the result of `{}`; add parentheses if that is intended",
+                            expression.to_string().trim(),
+                            inner.to_string().trim()
+                        );
+                        self.found.push((Rule::ChainedComparison, line, message));
+                    }
+                }
+                _ => {}
+            }
+            let children: Vec<&SyntaxNode> = expression.nodes().collect();
+            pending.extend(children.into_iter().rev());
+        }
+    }
+
+    fn read(&mut self, name: &str, line: usize) {
+        let message = match self.bindings.get(name) {
+            Some(&index) => {
+                let declaration = &mut self.declarations[index];
+                declaration.read = true;
+                if declaration.initialized {
+                    return;
+                }
+                format!("`{}` is read before it is assigned a value", name)
+            }
+            None if self.declared.contains(name) => {
+                format!("`{}` is read before it is declared", name)
+            }
+            None => return,
+        };
+        self.found.push((Rule::ReadBeforeAssignment, line, message));
+    }
+
+    /** Reports the declarations nothing read, unless their name starts with `_` */
+    fn finish(&mut self) {
+        for declaration in &self.declarations {
+            if !declaration.read && !declaration.name.starts_with('_') {
+                let message = format!("`{}` is declared but never read", declaration.name);
+                self.found
+                    .push((Rule::UnusedVariable, declaration.line, message));
+            }
+        }
+    }
+}
+
+/** Whether `node` is an ungrouped `==`, `!=`, `<`, `<=`, `>` or `>=` */
+fn is_comparison(node: &SyntaxNode) -> bool {
+    node.kind == SyntaxKind::Binary
+        && node.tokens().next().is_some_and(|operator| {
+            matches!(
+                operator.token.ty,
+                TokenType::EqualEqual
+                    | TokenType::BangEqual
+                    | TokenType::Less
+                    | TokenType::LessEqual
+                    | TokenType::Greater
+                    | TokenType::GreaterEqual
+            )
+        })
+}
+
+/** The name `node` reads, looking through parentheses */
+fn variable(node: &SyntaxNode) -> Option<&String> {
+    match node.kind {
+        SyntaxKind::Variable => node.tokens().next().map(|token| &token.token.lexeme),
+        SyntaxKind::Grouping => node.nodes().next().and_then(variable),
+        _ => None,
+    }
+}
+
+/// Finds `// lint:allow(rule, ...)` comments. One at the end of a line
+/// applies to that line; one on a line of its own applies to the next token.
+fn allowed(source: &str) -> HashSet<(usize, Rule)> {
+    let (tokens, _) = cst::tokens(source);
+    let mut allowed = HashSet::new();
+    for token in &tokens {
+        let comments = token.leading.iter().chain(&token.trailing);
+        for comment in comments.filter(|trivia| trivia.kind == TriviaKind::Comment) {
+            let rules = comment
+                .text
+                .trim_start_matches('/')
+                .trim()
+                .strip_prefix("lint:allow(")
+                .and_then(|rest| rest.split_once(')'))
+                .map_or("", |(rules, _)| rules);
+            for rule in rules
+                .split(',')
+                .filter_map(|rule| Rule::from_name(rule.trim()))
+            {
+                allowed.insert((token.token.line, rule));
+            }
+        }
+    }
+    allowed
+}
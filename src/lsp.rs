@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::Range;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationType, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as RequestType,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, Diagnostic, DiagnosticSeverity,
+    DocumentSymbol, DocumentSymbolParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    OneOf, Position, PublishDiagnosticsParams, ServerCapabilities, SymbolKind,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use rusting_interpreters::ast::AstNode;
+use rusting_interpreters::cst::{self, SyntaxElement, SyntaxKind, SyntaxNode};
+use rusting_interpreters::error::SyntaxError;
+use rusting_interpreters::scanner::{Scanner, Token, TokenType};
+use rusting_interpreters::{optimizer, stdlib};
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Serves the Language Server Protocol over stdin and stdout until the
+/// client asks it to shut down.
+pub fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+
+    let mut server = Server::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = server.request(request);
+                connection.sender.send(response.into())?;
+            }
+            Message::Notification(notification) => {
+                if let Some(diagnostics) = server.notification(notification)? {
+                    connection.sender.send(diagnostics.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    /* The writer thread only finishes once the connection is gone */
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..ServerCapabilities::default()
+    }
+}
+
+struct Server {
+    documents: HashMap<Url, Document>,
+    keywords: Vec<String>,
+    /** The standard library's natives, with their arity */
+    natives: Vec<(String, usize)>,
+}
+
+impl Server {
+    fn new() -> Server {
+        let mut keywords: Vec<String> = Scanner::new(String::new())
+            .keywords()
+            .map(str::to_string)
+            .collect();
+        keywords.sort();
+
+        Server {
+            documents: HashMap::new(),
+            keywords,
+            natives: stdlib::native_names(),
+        }
+    }
+
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let params = request.params;
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => serde_json::from_value(params)
+                .and_then(|params| serde_json::to_value(self.hover(params))),
+            GotoDefinition::METHOD => serde_json::from_value(params)
+                .and_then(|params| serde_json::to_value(self.definition(params))),
+            DocumentSymbolRequest::METHOD => serde_json::from_value(params)
+                .and_then(|params| serde_json::to_value(self.symbols(params))),
+            Completion::METHOD => serde_json::from_value(params)
+                .and_then(|params| serde_json::to_value(self.completion(params))),
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request {}", method),
+                )
+            }
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err(error) => Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string()),
+        }
+    }
+
+    /** Keeps track of open documents; returns the diagnostics to publish for one that changed */
+    fn notification(&mut self, notification: Notification) -> LspResult<Option<Notification>> {
+        let (uri, text) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                (params.text_document.uri, Some(params.text_document.text))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                /* With full sync the last change holds the whole document */
+                let text = params
+                    .content_changes
+                    .into_iter()
+                    .last()
+                    .map(|change| change.text);
+                (params.text_document.uri, text)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                (params.text_document.uri, None)
+            }
+            _ => return Ok(None),
+        };
+
+        let diagnostics = match text {
+            Some(text) => {
+                let document = Document::new(text);
+                let diagnostics = document.diagnostics();
+                self.documents.insert(uri.clone(), document);
+                diagnostics
+            }
+            None if self.documents.contains_key(&uri) => return Ok(None),
+            None => Vec::new(),
+        };
+
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        Ok(Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let (document, token, span) = self.token_at(&params.text_document_position_params)?;
+        let text = match token.ty {
+            TokenType::Ident => {
+                let name = token.lexeme.as_str();
+                if let Some(declaration) = document.declaration(name, span.start) {
+                    format!("```lox\n{}\n```\n{}", declaration.source, declaration.kind)
+                } else if let Some((_, arity)) = self.native(name) {
+                    format!(
+                        "```lox\n{}\n```\nnative function, {}",
+                        name,
+                        arguments(*arity)
+                    )
+                } else {
+                    format!("`{}` is not declared in this file", name)
+                }
+            }
+            TokenType::Number | TokenType::String | TokenType::True | TokenType::False => {
+                literal_kind(token)?.to_string()
+            }
+            _ => return None,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: text,
+            }),
+            range: Some(document.range(span.clone())),
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = &params.text_document_position_params;
+        let (document, token, span) = self.token_at(position)?;
+        if token.ty != TokenType::Ident {
+            return None;
+        }
+
+        let declaration = document.declaration(&token.lexeme, span.start)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            position.text_document.uri.clone(),
+            document.range(declaration.name.clone()),
+        )))
+    }
+
+    fn symbols(&self, params: DocumentSymbolParams) -> Option<Vec<DocumentSymbol>> {
+        let document = self.documents.get(&params.text_document.uri)?;
+        let symbols = document
+            .declarations
+            .iter()
+            .map(|declaration| {
+                #[allow(deprecated)]
+                DocumentSymbol {
+                    name: declaration.name(&document.text).to_string(),
+                    detail: Some(declaration.kind.to_string()),
+                    kind: declaration.symbol_kind,
+                    tags: None,
+                    deprecated: None,
+                    range: document.range(declaration.statement.clone()),
+                    selection_range: document.range(declaration.name.clone()),
+                    children: None,
+                }
+            })
+            .collect();
+        Some(symbols)
+    }
+
+    fn completion(&self, params: lsp_types::CompletionParams) -> Vec<CompletionItem> {
+        let mut items: Vec<CompletionItem> = Vec::new();
+        let mut seen = Vec::new();
+
+        if let Some(document) = self
+            .documents
+            .get(&params.text_document_position.text_document.uri)
+        {
+            for declaration in document.declarations.iter().rev() {
+                let name = declaration.name(&document.text);
+                if !seen.contains(&name) {
+                    seen.push(name);
+                    items.push(CompletionItem {
+                        label: name.to_string(),
+                        kind: Some(match declaration.symbol_kind {
+                            SymbolKind::FUNCTION => CompletionItemKind::FUNCTION,
+                            SymbolKind::CLASS => CompletionItemKind::CLASS,
+                            _ => CompletionItemKind::VARIABLE,
+                        }),
+                        detail: Some(declaration.kind.clone()),
+                        ..CompletionItem::default()
+                    });
+                }
+            }
+        }
+        for (name, arity) in &self.natives {
+            items.push(CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(format!("native function, {}", arguments(*arity))),
+                ..CompletionItem::default()
+            });
+        }
+        for keyword in &self.keywords {
+            items.push(CompletionItem {
+                label: keyword.clone(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..CompletionItem::default()
+            });
+        }
+        items
+    }
+
+    /** The document and the token under or just before the cursor */
+    fn token_at(
+        &self,
+        position: &TextDocumentPositionParams,
+    ) -> Option<(&Document, &Token, &Range<usize>)> {
+        let document = self.documents.get(&position.text_document.uri)?;
+        let offset = document.offset(position.position);
+        let (token, span) = document
+            .tokens
+            .iter()
+            .find(|(token, span)| token.ty != TokenType::Eof && span.contains(&offset))
+            .or_else(|| document.tokens.iter().find(|(_, span)| span.end == offset))?;
+        Some((document, token, span))
+    }
+
+    fn native(&self, name: &str) -> Option<&(String, usize)> {
+        self.natives.iter().find(|(native, _)| native == name)
+    }
+}
+
+/// A `var` statement, or a `fun` or `class` the parser does not support yet
+/// and kept as an error node, so documents still get symbols while they
+/// have syntax errors.
+struct Declaration {
+    symbol_kind: SymbolKind,
+    /** What hover shows, e.g. `number` for `var a = 1;` */
+    kind: String,
+    /** The source of the declaration up to its `;` or `{` */
+    source: String,
+    name: Range<usize>,
+    statement: Range<usize>,
+}
+
+impl Declaration {
+    fn name<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.name.clone()]
+    }
+}
+
+struct Document {
+    text: String,
+    /** Byte offset of the start of each line */
+    line_starts: Vec<usize>,
+    tokens: Vec<(Token, Range<usize>)>,
+    errors: Vec<SyntaxError>,
+    declarations: Vec<Declaration>,
+}
+
+impl Document {
+    fn new(text: String) -> Document {
+        let (tree, errors) = cst::parse(&text);
+
+        let mut tokens = Vec::new();
+        let mut declarations = Vec::new();
+        let mut offset = 0;
+        for child in &tree.children {
+            let first = tokens.len();
+            spans(child, &mut offset, &mut tokens);
+            if let SyntaxElement::Node(statement) = child {
+                declarations.extend(declaration(&text, statement, &tokens[first..]));
+            }
+        }
+
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Document {
+            text,
+            line_starts,
+            tokens,
+            errors,
+            declarations,
+        }
+    }
+
+    /** The declaration `name` refers to at `offset`: the last one before it, or else the first */
+    fn declaration(&self, name: &str, offset: usize) -> Option<&Declaration> {
+        let matching: Vec<&Declaration> = self
+            .declarations
+            .iter()
+            .filter(|declaration| declaration.name(&self.text) == name)
+            .collect();
+        matching
+            .iter()
+            .rev()
+            .find(|declaration| declaration.name.start <= offset)
+            .or(matching.first())
+            .copied()
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors
+            .iter()
+            .map(|error| Diagnostic {
+                range: self.error_range(error),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("rlox".to_string()),
+                message: error.message.clone(),
+                ..Diagnostic::default()
+            })
+            .collect()
+    }
+
+    /** The token the error points at, or its whole line when it has no location */
+    fn error_range(&self, error: &SyntaxError) -> lsp_types::Range {
+        let at = |token: &&(Token, Range<usize>)| {
+            let (token, _) = token;
+            token.line == error.line
+                && match error.location.as_str() {
+                    " at end" => token.ty == TokenType::Eof,
+                    location => location == format!(" at '{}'", token.lexeme),
+                }
+        };
+        if let Some((_, span)) = self.tokens.iter().find(at) {
+            return self.range(span.clone());
+        }
+
+        let line = error.line.saturating_sub(1).min(self.line_starts.len() - 1);
+        let start = self.line_starts[line];
+        let end = self.text[start..]
+            .find('\n')
+            .map_or(self.text.len(), |end| start + end);
+        self.range(start..end)
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        /* LSP counts columns in UTF-16 code units */
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    fn range(&self, span: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(span.start), self.position(span.end))
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        let start = match self.line_starts.get(position.line as usize) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= position.character as usize || c == '\n' {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+}
+
+/** Appends the tokens under `element` with their byte ranges, counting from `offset` */
+fn spans(element: &SyntaxElement, offset: &mut usize, tokens: &mut Vec<(Token, Range<usize>)>) {
+    let mut pending = vec![element];
+    while let Some(element) = pending.pop() {
+        match element {
+            SyntaxElement::Node(node) => pending.extend(node.children.iter().rev()),
+            SyntaxElement::Token(token) => {
+                *offset += token
+                    .leading
+                    .iter()
+                    .map(|trivia| trivia.text.len())
+                    .sum::<usize>();
+                let start = *offset;
+                *offset += token.token.lexeme.len();
+                tokens.push((token.token.clone(), start..*offset));
+                *offset += token
+                    .trailing
+                    .iter()
+                    .map(|trivia| trivia.text.len())
+                    .sum::<usize>();
+            }
+        }
+    }
+}
+
+/** The declaration `statement` makes, given the tokens it spans */
+fn declaration(
+    text: &str,
+    statement: &SyntaxNode,
+    tokens: &[(Token, Range<usize>)],
+) -> Option<Declaration> {
+    let [(keyword, keyword_span), (name, name_span), ..] = tokens else {
+        return None;
+    };
+    if name.ty != TokenType::Ident {
+        return None;
+    }
+    let (symbol_kind, kind) = match (statement.kind, keyword.ty) {
+        (SyntaxKind::VarDecl, _) => (SymbolKind::VARIABLE, initializer_kind(statement)),
+        (SyntaxKind::Error, TokenType::Var) => (SymbolKind::VARIABLE, "variable"),
+        (SyntaxKind::Error, TokenType::Fun) => (SymbolKind::FUNCTION, "function"),
+        (SyntaxKind::Error, TokenType::Class) => (SymbolKind::CLASS, "class"),
+        _ => return None,
+    };
+
+    /* A `fun` or `class` ends at its body; the rest of the statement is the body */
+    let end = tokens
+        .iter()
+        .find(|(token, _)| matches!(token.ty, TokenType::Semicolon | TokenType::LeftBrace))
+        .unwrap_or(&tokens[tokens.len() - 1]);
+    let statement = keyword_span.start..end.1.end;
+    Some(Declaration {
+        symbol_kind,
+        kind: kind.to_string(),
+        source: text[statement.clone()]
+            .trim_end_matches('{')
+            .trim()
+            .to_string(),
+        name: name_span.clone(),
+        statement,
+    })
+}
+
+/** The type of a `var`'s value when it can be worked out without running anything */
+fn initializer_kind(statement: &SyntaxNode) -> &'static str {
+    let Some(initializer) = statement.nodes().next() else {
+        return "nil";
+    };
+    match initializer.to_ast().map(optimizer::fold) {
+        Some(AstNode::Literal { value }) => value.type_name(),
+        _ => "variable",
+    }
+}
+
+fn literal_kind(token: &Token) -> Option<&'static str> {
+    match token.ty {
+        TokenType::Number => Some("number"),
+        TokenType::String => Some("string"),
+        TokenType::True | TokenType::False => Some("boolean"),
+        _ => None,
+    }
+}
+
+fn arguments(arity: usize) -> String {
+    match arity {
+        1 => "1 argument".to_string(),
+        arity => format!("{} arguments", arity),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> lsp_types::Range {
+        lsp_types::Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn positions_count_columns_in_utf16_code_units() {
+        let document = Document::new("é😀x\nab".to_string());
+        assert_eq!(document.position(0), Position::new(0, 0));
+        assert_eq!(document.position(2), Position::new(0, 1));
+        assert_eq!(document.position(6), Position::new(0, 3));
+        assert_eq!(document.position(8), Position::new(1, 0));
+        assert_eq!(document.position(10), Position::new(1, 2));
+
+        for offset in [0, 2, 6, 7, 8, 9, 10] {
+            assert_eq!(document.offset(document.position(offset)), offset);
+        }
+        /* Past the end of a line or of the document */
+        assert_eq!(document.offset(Position::new(0, 99)), 7);
+        assert_eq!(document.offset(Position::new(9, 0)), 10);
+    }
+
+    #[test]
+    fn errors_point_at_their_token_or_else_their_line() {
+        let document = Document::new("print @ 1;\nprint ;\nprint 2".to_string());
+        let ranges: Vec<(String, lsp_types::Range)> = document
+            .errors
+            .iter()
+            .map(|error| (error.message.clone(), document.error_range(error)))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                ("Unexpected character.".to_string(), range((0, 0), (0, 10))),
+                ("Expect expression.".to_string(), range((1, 6), (1, 7))),
+                ("Expect ';' after value.".to_string(), range((2, 7), (2, 7))),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_declarations_in_the_syntax_tree_and_in_unparsed_statements() {
+        let text = "var a = 1 + 2;\nfun f(x) { return x; }\nclass C {}\nvar b;\nvar c = a;";
+        let document = Document::new(text.to_string());
+        let declarations: Vec<(&str, &str, &str)> = document
+            .declarations
+            .iter()
+            .map(|declaration| {
+                (
+                    declaration.name(text),
+                    declaration.kind.as_str(),
+                    declaration.source.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            declarations,
+            [
+                ("a", "number", "var a = 1 + 2;"),
+                ("f", "function", "fun f(x)"),
+                ("C", "class", "class C"),
+                ("b", "nil", "var b;"),
+                ("c", "variable", "var c = a;"),
+            ]
+        );
+        assert_eq!(document.declaration("a", text.len()).unwrap().name, 4..5);
+    }
+
+    #[test]
+    fn deeply_nested_documents_get_a_diagnostic_instead_of_a_crash() {
+        let text = format!("print {}1{};", "(".repeat(50_000), ")".repeat(50_000));
+        let document = Document::new(text);
+        assert_eq!(document.errors[0].message, "Expression nests too deeply.");
+    }
+}
@@ -1,88 +1,96 @@
-extern crate core;
-
+use std::env;
 use std::io;
-use std::io::{BufRead, Write};
-use std::path::Path;
-use std::process;
 use std::process::exit;
-use std::sync::atomic::Ordering::Relaxed;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::{env, fs};
 
-use scanner::Scanner;
+use rusting_interpreters::{Capability, Interpreter, IntoLox, LoxError};
 
-use crate::environment::Environment;
-use crate::parser::Parser;
+use crate::cli::{Command, Script};
+use crate::repl::run_prompt;
 
-mod ast;
-mod environment;
-mod parser;
-mod scanner;
+mod cli;
+mod commands;
+mod dump;
+mod lsp;
+mod repl;
+mod session;
+mod watch;
 
-// Global flag to indicate if an error has occurred
-static HAD_ERROR: AtomicBool = AtomicBool::new(false);
+/* Exit codes from sysexits.h */
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+const EX_CONFIG: i32 = 78;
 
 fn main() -> Result<(), io::Error> {
-    let args: Vec<String> = env::args().collect();
-
-    match args.len() {
-        n if n > 2 => {
-            println!("Usage: rlox [script]");
-            process::exit(64);
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match cli::parse(&args) {
+        Ok(Command::Repl) => run_prompt()?,
+        Ok(Command::Help) => println!("{}", cli::USAGE),
+        Ok(Command::Watch { path, args }) => watch::run(&path, &args),
+        Ok(Command::Fmt { paths, check }) => commands::format_files(&paths, check),
+        Ok(Command::Lint { paths }) => commands::lint_files(&paths),
+        Ok(Command::Highlight { script, html }) => commands::highlight_script(&script, html),
+        Ok(Command::Lsp) => {
+            if let Err(error) = lsp::run() {
+                eprintln!("Language server failed: {}", error);
+                exit(EX_SOFTWARE);
+            }
+        }
+        Ok(Command::Run {
+            script,
+            args,
+            dump_tokens,
+            dump_ast,
+        }) => run_script(&script, args, dump_tokens, dump_ast),
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            exit(EX_USAGE);
         }
-        2 => run_file(&args[1]),
-        _ => run_prompt()?,
     }
 
     Ok(())
 }
 
-fn run_file(path: &str) {
-    let content = fs::read_to_string(Path::new(path)).unwrap();
-    let mut environment = Environment::new();
-    run(content, &mut environment);
-
-    if HAD_ERROR.load(Relaxed) {
-        exit(65)
-    }
-}
-
-fn run_prompt() -> io::Result<()> {
-    let stdin = io::stdin();
-    let mut environment = Environment::new();
-
-    for line in stdin.lock().lines() {
-        print!("> ");
-        io::stdout().flush()?; // Flush to ensure the prompt is displayed before waiting for input
-
-        match line {
-            Ok(input) => {
-                run(input, &mut environment);
-                HAD_ERROR.store(false, Relaxed);
+/** Runs or dumps `script`, exiting with a sysexits code if anything fails */
+fn run_script(script: &Script, args: Vec<String>, dump_tokens: bool, dump_ast: bool) {
+    let source = match script.read() {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Could not read script: {}", error);
+            exit(EX_IOERR)
+        }
+    };
+
+    if dump_tokens || dump_ast {
+        match dump::run(&source, dump_tokens, dump_ast) {
+            Ok(true) => return,
+            Ok(false) => exit(EX_DATAERR),
+            Err(error) => {
+                eprintln!("{}", error);
+                exit(exit_code(&error))
             }
-            Err(_) => break,
         }
     }
 
-    Ok(())
-}
-
-fn run(input: String, environment: &mut Environment) {
-    let mut scanner = Scanner::new(input);
-    let tokens = scanner.scan_tokens();
-    let mut parser = Parser::new(tokens.clone());
-    let expression = parser.parse();
+    let mut interpreter = Interpreter::builder()
+        .capabilities(&Capability::ALL)
+        .build();
+    interpreter.set_global("args", args.into_lox());
 
-    for exp in expression {
-        let value = exp.evaluate(environment);
+    if let Err(error) = interpreter.eval_str(&source) {
+        eprintln!("{}", error);
+        exit(exit_code(&error))
     }
 }
 
-fn error(line: usize, message: &str) {
-    report(line, "", message);
-}
-
-fn report(line: usize, where_: &str, message: &str) {
-    eprintln!("[line {}] Error{}: {}", line, where_, message);
-    HAD_ERROR.store(true, Ordering::Relaxed);
+fn exit_code(error: &LoxError) -> i32 {
+    match error {
+        LoxError::Io(_) => EX_IOERR,
+        LoxError::Syntax(_) => EX_DATAERR,
+        LoxError::Runtime { .. } | LoxError::LimitExceeded(_) | LoxError::Interrupted => {
+            EX_SOFTWARE
+        }
+    }
 }
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::LiteralValue;
+use crate::convert::{FromLox, IntoLox};
+use crate::interner::Symbol;
+
+/// What a native function hands back to the script. An `Err` becomes a
+/// runtime error reported on the line of the call.
+pub type NativeResult = Result<LiteralValue, String>;
+
+type NativeFn = dyn Fn(&[LiteralValue]) -> NativeResult;
+
+/** A function implemented in Rust and callable from Lox */
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: Symbol,
+    pub arity: usize,
+    function: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &str, arity: usize, function: F) -> NativeFunction
+    where
+        F: Fn(&[LiteralValue]) -> NativeResult + 'static,
+    {
+        NativeFunction {
+            name: Symbol::intern(name),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+
+    /** Calls the function; the caller is responsible for checking the arity */
+    pub fn call(&self, arguments: &[LiteralValue]) -> NativeResult {
+        (self.function)(arguments)
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// The return type of a typed native: either a plain value or a
+/// `Result` whose error becomes a runtime error.
+pub trait IntoNativeResult {
+    fn into_native_result(self) -> NativeResult;
+}
+
+impl<T: IntoLox> IntoNativeResult for T {
+    fn into_native_result(self) -> NativeResult {
+        Ok(self.into_lox())
+    }
+}
+
+impl<T: IntoLox> IntoNativeResult for Result<T, String> {
+    fn into_native_result(self) -> NativeResult {
+        self.map(IntoLox::into_lox)
+    }
+}
+
+/// A Rust closure with typed parameters that can be wrapped as a
+/// `NativeFunction`. Each argument is converted with `FromLox`, so a
+/// mismatched argument is reported before the closure runs.
+pub trait TypedNative<Args> {
+    fn into_native(self, name: &str) -> NativeFunction;
+}
+
+macro_rules! typed_native {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> TypedNative<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoNativeResult,
+            $($arg: FromLox,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables, unused_assignments)]
+            fn into_native(self, name: &str) -> NativeFunction {
+                let arity = <[&str]>::len(&[$(stringify!($arg)),*]);
+                let function_name = name.to_string();
+                NativeFunction::new(name, arity, move |arguments| {
+                    let mut index = 0;
+                    $(
+                        let $arg = $arg::from_lox(&arguments[index]).map_err(|error| {
+                            format!(
+                                "Argument {} of '{}': {}",
+                                index + 1,
+                                function_name,
+                                error
+                            )
+                        })?;
+                        index += 1;
+                    )*
+                    self($($arg),*).into_native_result()
+                })
+            }
+        }
+    };
+}
+
+typed_native!();
+typed_native!(A);
+typed_native!(A, B);
+typed_native!(A, B, C);
+typed_native!(A, B, C, D);
+typed_native!(A, B, C, D, E);
+typed_native!(A, B, C, D, E, G);
@@ -0,0 +1,88 @@
+use crate::ast::AstNode;
+use crate::ast::AstNode::{
+    Binary, Call, Expression, Get, Grouping, Literal, StmtExpression, StmtPrint, StmtVariable,
+    Unary, VariableExpression,
+};
+
+/** Runs the constant folding pass over every parsed statement */
+pub fn optimize(statements: Vec<AstNode>) -> Vec<AstNode> {
+    statements.into_iter().map(fold).collect()
+}
+
+/// Folds operators whose operands are literals and strips wrapper nodes.
+///
+/// Operations that would fail (like `"a" - 1`) are left in the tree so the
+/// error is still raised at run time, with the operator's line. Chains like
+/// `a + b + c` are folded in a loop; other nesting is bounded by
+/// [`MAX_NESTING`](crate::parser::MAX_NESTING).
+pub fn fold(node: AstNode) -> AstNode {
+    match node {
+        Binary { .. } | Call { .. } | Get { .. } => {
+            let (first, links) = node.unchain();
+            let mut folded = fold(first);
+            for mut link in links {
+                link.set_left(folded);
+                folded = fold_link(link);
+            }
+            folded
+        }
+        Unary { operator, right } => {
+            let right = fold(*right);
+            if let Literal { value } = &right {
+                if let Ok(value) = AstNode::unary(&operator, value.clone()) {
+                    return Literal { value };
+                }
+            }
+            Unary {
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Grouping { node } => fold(*node),
+        Expression { value } => fold(*value),
+        StmtExpression { value } => StmtExpression {
+            value: Box::new(fold(*value)),
+        },
+        StmtPrint { value } => StmtPrint {
+            value: Box::new(fold(*value)),
+        },
+        StmtVariable { name, initializer } => StmtVariable {
+            name,
+            initializer: initializer.map(|value| Box::new(fold(*value))),
+        },
+        Literal { .. } | VariableExpression { .. } => node,
+    }
+}
+
+/** Folds one link of a chain whose left operand is already folded */
+fn fold_link(node: AstNode) -> AstNode {
+    match node {
+        Binary {
+            left,
+            operator,
+            right,
+        } => {
+            let right = fold(*right);
+            if let (Literal { value: left }, Literal { value: right }) = (&*left, &right) {
+                if let Ok(value) = AstNode::binary(&operator, left.clone(), right.clone()) {
+                    return Literal { value };
+                }
+            }
+            Binary {
+                left,
+                operator,
+                right: Box::new(right),
+            }
+        }
+        Call {
+            callee,
+            paren,
+            arguments,
+        } => Call {
+            callee,
+            paren,
+            arguments: arguments.into_iter().map(fold).collect(),
+        },
+        node => node,
+    }
+}
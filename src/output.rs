@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// An in-memory sink for program output. Clones share the same buffer, so
+/// keep one and hand the other to the interpreter to read what was printed.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub fn new() -> SharedBuffer {
+        SharedBuffer::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+
+    /** Returns everything printed so far and empties the buffer */
+    pub fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.0.borrow_mut());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hands each complete line of program output to a closure, for example
+/// to forward it to a logger: `LineSink::new(|line| log::info!("{}", line))`.
+pub struct LineSink<F: FnMut(&str)> {
+    pending: Vec<u8>,
+    callback: F,
+}
+
+impl<F: FnMut(&str)> LineSink<F> {
+    pub fn new(callback: F) -> LineSink<F> {
+        LineSink {
+            pending: Vec::new(),
+            callback,
+        }
+    }
+
+    fn emit(&mut self, line: &[u8]) {
+        (self.callback)(&String::from_utf8_lossy(line));
+    }
+}
+
+impl<F: FnMut(&str)> Write for LineSink<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.emit(&line[..end]);
+        }
+        Ok(buf.len())
+    }
+
+    /** Emits a trailing line that has no newline yet */
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.emit(&line);
+        }
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&str)> Drop for LineSink<F> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -1,49 +1,144 @@
-use anyhow::Error;
-
-use crate::ast::AstNode::{
-    Binary, Grouping, Literal, StmtExpression, StmtPrint, StmtVariable, Unary, VariableExpression,
+use crate::ast::AstNode;
+use crate::cst::SyntaxKind::{
+    Binary, Call, Error, ExprStmt, Get, Grouping, Literal, PrintStmt, Program, Unary, VarDecl,
+    Variable,
 };
-use crate::ast::{AstNode, LiteralValue};
-use crate::scanner::{Token, TokenType, TokenValue};
-
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+use crate::error::SyntaxError;
+use crate::scanner::{Token, TokenType};
+
+type ParseResult = Result<SyntaxNode, SyntaxError>;
+
+/// How deeply groupings, unary operators and call arguments may nest
+/// before parsing fails. Every pass over the tree recurses into them, so
+/// this keeps untrusted input from overflowing the stack in the parser,
+/// the optimizer, the lowering or the evaluator; 100 levels still fit a
+/// 2 MiB thread stack in a debug build. Left-leaning chains like
+/// `a + b + c` or `f()()` are walked in a loop and do not count.
+pub const MAX_NESTING: usize = 100;
+
+/// Parses tokens into a concrete syntax tree that keeps every token, and
+/// lowers it to `AstNode`s for the interpreter.
 pub struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<SyntaxToken>,
     current: usize,
+    /** How many levels of the expression being parsed enclose the current token */
+    depth: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser { current: 0, tokens }
+        Parser::lossless(tokens.into_iter().map(SyntaxToken::from).collect())
+    }
+
+    /** A parser over tokens that carry their trivia, see `cst::tokens` */
+    pub fn lossless(tokens: Vec<SyntaxToken>) -> Parser {
+        Parser {
+            current: 0,
+            depth: 0,
+            tokens,
+        }
     }
 
-    pub fn parse(&mut self) -> Vec<AstNode> {
-        let mut statements: Vec<AstNode> = Vec::new();
+    pub fn parse(&mut self) -> Result<Vec<AstNode>, Vec<SyntaxError>> {
+        let (tree, errors) = self.parse_tree();
+        if errors.is_empty() {
+            Ok(tree.nodes().map(lower).collect())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses every statement into a `Program` node. Statements with errors
+    /// become `Error` nodes holding the tokens skipped while recovering.
+    pub fn parse_tree(&mut self) -> (SyntaxNode, Vec<SyntaxError>) {
+        let mut statements = Vec::new();
+        let mut errors: Vec<SyntaxError> = Vec::new();
         while !self.is_at_end() {
-            statements.push(*self.declaration());
+            let start = self.current;
+            self.depth = 0;
+            match self.declaration() {
+                Ok(statement) => statements.push(statement.into()),
+                Err(error) => {
+                    errors.push(error);
+                    self.synchronize();
+                    let skipped = self.tokens[start..self.current]
+                        .iter()
+                        .cloned()
+                        .map(Into::into)
+                        .collect();
+                    statements.push(SyntaxNode::new(Error, skipped).into());
+                }
+            }
+        }
+        statements.push(self.peek_token().clone().into());
+
+        (SyntaxNode::new(Program, statements), errors)
+    }
+
+    /** Parses the tokens as one expression with nothing after it */
+    pub fn parse_expression(&mut self) -> Result<AstNode, SyntaxError> {
+        self.depth = 0;
+        let expression = self.expression()?;
+        if !self.is_at_end() {
+            return Err(SyntaxError::at(self.peek(), "Expect end of expression."));
         }
+        Ok(lower(&expression))
+    }
 
-        statements
+    /** Skips tokens until the start of the next statement after an error */
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().ty == TokenType::Semicolon {
+                return;
+            }
+            match self.peek().ty {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
     }
 
-    fn expression(&mut self) -> Box<AstNode> {
-        self.equality()
+    fn expression(&mut self) -> ParseResult {
+        let depth = self.depth;
+        self.nest()?;
+        let expression = self.equality()?;
+        self.depth = depth;
+        Ok(expression)
     }
 
-    fn equality(&mut self) -> Box<AstNode> {
-        let mut exp = self.comparison();
+    /// Goes one level deeper into the expression, failing past
+    /// `MAX_NESTING`. Callers restore the depth when they are done; after an
+    /// error it is reset at the next statement.
+    fn nest(&mut self) -> Result<(), SyntaxError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(SyntaxError::at(self.peek(), "Expression nests too deeply."));
+        }
+        Ok(())
+    }
+
+    fn equality(&mut self) -> ParseResult {
+        let mut exp = self.comparison()?;
         while let Some(operator) = self.match_tokens(&[TokenType::BangEqual, TokenType::EqualEqual])
         {
-            let right = self.comparison();
-            exp = Box::new(Binary {
-                left: exp,
-                operator,
-                right,
-            });
+            let right = self.comparison()?;
+            exp = node(Binary, vec![exp.into(), operator.into(), right.into()]);
         }
-        exp
+        Ok(exp)
     }
 
-    fn match_tokens(&mut self, types: &[TokenType]) -> Option<Token> {
+    fn match_tokens(&mut self, types: &[TokenType]) -> Option<SyntaxToken> {
         for &token_type in types {
             if self.check(token_type) {
                 return Some(self.advance().clone());
@@ -59,11 +154,11 @@ impl Parser {
         self.peek().ty == token_type
     }
 
-    fn advance(&mut self) -> &Token {
+    fn advance(&mut self) -> &SyntaxToken {
         if !self.is_at_end() {
             self.current += 1;
         }
-        self.previous()
+        &self.tokens[self.current - 1]
     }
 
     fn is_at_end(&self) -> bool {
@@ -71,185 +166,193 @@ impl Parser {
     }
 
     fn peek(&self) -> &Token {
-        &self.tokens[self.current.clone()]
+        &self.peek_token().token
+    }
+
+    fn peek_token(&self) -> &SyntaxToken {
+        &self.tokens[self.current]
     }
 
     fn previous(&self) -> &Token {
-        &self.tokens[&self.current - 1]
+        &self.tokens[self.current - 1].token
     }
 
-    fn comparison(&mut self) -> Box<AstNode> {
-        let mut expr = self.term();
+    fn comparison(&mut self) -> ParseResult {
+        let mut expr = self.term()?;
         while let Some(operator) = self.match_tokens(&[
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
-            let right = self.term();
-            expr = Box::new(Binary {
-                left: expr,
-                operator,
-                right,
-            });
+            let right = self.term()?;
+            expr = node(Binary, vec![expr.into(), operator.into(), right.into()]);
         }
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Box<AstNode> {
-        let mut expr = self.factor();
+    fn term(&mut self) -> ParseResult {
+        let mut expr = self.factor()?;
         while let Some(operator) = self.match_tokens(&[TokenType::Minus, TokenType::Plus]) {
-            let right = self.factor();
-            expr = Box::new(Binary {
-                left: expr,
-                operator,
-                right,
-            });
+            let right = self.factor()?;
+            expr = node(Binary, vec![expr.into(), operator.into(), right.into()]);
         }
-        expr
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Box<AstNode> {
-        let mut expr = self.unary();
+    fn factor(&mut self) -> ParseResult {
+        let mut expr = self.unary()?;
         while let Some(operator) = self.match_tokens(&[TokenType::Slash, TokenType::Star]) {
-            let right = self.unary();
-            expr = Box::new(Binary {
-                left: expr,
-                operator,
-                right,
-            });
+            let right = self.unary()?;
+            expr = node(Binary, vec![expr.into(), operator.into(), right.into()]);
         }
-        expr
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Box<AstNode> {
+    fn unary(&mut self) -> ParseResult {
         if let Some(operator) = self.match_tokens(&[TokenType::Bang, TokenType::Minus]) {
-            let right = self.unary();
-            return Box::new(Unary { operator, right });
+            let depth = self.depth;
+            self.nest()?;
+            let right = self.unary()?;
+            self.depth = depth;
+            return Ok(node(Unary, vec![operator.into(), right.into()]));
         }
-        self.primary()
+        self.call()
     }
 
-    fn primary(&mut self) -> Box<AstNode> {
-        if self.match_tokens(&[TokenType::False]).is_some() {
-            return Box::new(Literal {
-                value: LiteralValue::False,
-            });
-        }
-        if self.match_tokens(&[TokenType::True]).is_some() {
-            return Box::new(Literal {
-                value: LiteralValue::True,
-            });
+    fn call(&mut self) -> ParseResult {
+        let mut expr = self.primary()?;
+        loop {
+            if let Some(paren) = self.match_tokens(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr, paren)?;
+            } else if let Some(dot) = self.match_tokens(&[TokenType::Dot]) {
+                let name = self.consume(TokenType::Ident, "Expect property name after '.'.")?;
+                expr = node(Get, vec![expr.into(), dot.into(), name.into()]);
+            } else {
+                break;
+            }
         }
-        if self.match_tokens(&[TokenType::Nil]).is_some() {
-            return Box::new(Literal {
-                value: LiteralValue::Nil,
-            });
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: SyntaxNode, open: SyntaxToken) -> ParseResult {
+        let mut children = vec![callee.into(), open.into()];
+        let mut arguments = 0;
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments >= 255 {
+                    return Err(SyntaxError::at(
+                        self.peek(),
+                        "Can't have more than 255 arguments.",
+                    ));
+                }
+                children.push(self.expression()?.into());
+                arguments += 1;
+                match self.match_tokens(&[TokenType::Comma]) {
+                    Some(comma) => children.push(comma.into()),
+                    None => break,
+                }
+            }
         }
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        children.push(paren.into());
 
-        if let Some(token) = self.match_tokens(&[TokenType::Number, TokenType::String]) {
-            return match token.literal {
-                Some(TokenValue::StringLiteral(ref value)) => Box::new(Literal {
-                    value: LiteralValue::String(value.clone()),
-                }),
-                Some(TokenValue::NumberLiteral(value)) => Box::new(Literal {
-                    value: LiteralValue::Number(value),
-                }),
-                _ => panic!("Unexpected token value!"), // Handle other cases or errors
-            };
+        Ok(node(Call, children))
+    }
+
+    fn primary(&mut self) -> ParseResult {
+        if let Some(token) = self.match_tokens(&[
+            TokenType::False,
+            TokenType::True,
+            TokenType::Nil,
+            TokenType::Number,
+            TokenType::String,
+        ]) {
+            return Ok(node(Literal, vec![token.into()]));
         }
 
-        if self.match_tokens(&[TokenType::Ident]).is_some() {
-            return VariableExpression {
-                value: self.previous().clone().lexeme,
-            }
-            .into();
+        if let Some(token) = self.match_tokens(&[TokenType::Ident]) {
+            return Ok(node(Variable, vec![token.into()]));
         }
 
-        if self.match_tokens(&[TokenType::LeftParen]).is_some() {
-            let expr = self.expression();
-            self.consume(
-                TokenType::RightParen,
-                "Expect ')' after expression.".to_string(),
-            )
-            .unwrap();
-            return Box::new(Grouping { node: expr });
+        if let Some(open) = self.match_tokens(&[TokenType::LeftParen]) {
+            let expr = self.expression()?;
+            let close = self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+            return Ok(node(Grouping, vec![open.into(), expr.into(), close.into()]));
         }
 
-        panic!("Unexpected token!") // Placeholder for error handling
+        Err(SyntaxError::at(self.peek(), "Expect expression."))
     }
 
-    fn consume(&mut self, token_type: TokenType, message: String) -> Result<Token, Error> {
+    fn consume(
+        &mut self,
+        token_type: TokenType,
+        message: &str,
+    ) -> Result<SyntaxToken, SyntaxError> {
         if self.check(token_type) {
             let token = self.advance();
             Ok(token.to_owned())
         } else {
-            self.error(self.peek().clone(), message)
-        }
-    }
-
-    fn error(&mut self, token: Token, message: String) -> Result<Token, Error> {
-        if token.ty == TokenType::Eof {
-            println!("{} at end {}", token.line, message);
-        } else {
-            println!("{} at {} {}", token.line, token.lexeme, message);
+            Err(SyntaxError::at(self.peek(), message))
         }
-        Ok(token)
     }
 
-    fn statement(&mut self) -> Box<AstNode> {
-        if self.match_tokens(&[TokenType::Print]).is_some() {
-            return self.print_statement();
+    fn statement(&mut self) -> ParseResult {
+        if let Some(print) = self.match_tokens(&[TokenType::Print]) {
+            return self.print_statement(print);
         }
 
         self.expression_statement()
     }
 
-    fn declaration(&mut self) -> Box<AstNode> {
-        if self.match_tokens(&[TokenType::Var]).is_some() {
-            return self.var_declaration();
+    fn declaration(&mut self) -> ParseResult {
+        if let Some(var) = self.match_tokens(&[TokenType::Var]) {
+            return self.var_declaration(var);
         }
         self.statement()
     }
 
-    fn var_declaration(&mut self) -> Box<AstNode> {
-        let name = self
-            .consume(TokenType::Ident, "Expected variable name".into())
-            .unwrap();
-        let mut initializer = None;
-        if self.match_tokens(&[TokenType::Equal]).is_some() {
-            initializer = Some(self.expression());
+    fn var_declaration(&mut self, var: SyntaxToken) -> ParseResult {
+        let name = self.consume(TokenType::Ident, "Expect variable name.")?;
+        let mut children = vec![var.into(), name.into()];
+        if let Some(equal) = self.match_tokens(&[TokenType::Equal]) {
+            children.push(equal.into());
+            children.push(self.expression()?.into());
         }
 
-        self.consume(
+        let semicolon = self.consume(
             TokenType::Semicolon,
-            "Expect ; after variable declaration".into(),
-        )
-        .expect("Expect ; after variable declaration");
+            "Expect ';' after variable declaration.",
+        )?;
+        children.push(semicolon.into());
 
-        StmtVariable {
-            name: match name.literal.unwrap() {
-                TokenValue::Identifier(name) => name,
-                _ => unreachable!(),
-            },
-            initializer,
-        }
-        .into()
+        Ok(node(VarDecl, children))
     }
 
-    fn print_statement(&mut self) -> Box<AstNode> {
-        let expression = self.expression();
-        self.consume(TokenType::Semicolon, "Expect ; after value.".into())
-            .unwrap();
+    fn print_statement(&mut self, print: SyntaxToken) -> ParseResult {
+        let expression = self.expression()?;
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
 
-        StmtPrint { value: expression }.into()
+        Ok(node(
+            PrintStmt,
+            vec![print.into(), expression.into(), semicolon.into()],
+        ))
     }
 
-    fn expression_statement(&mut self) -> Box<AstNode> {
-        let expression = self.expression();
-        self.consume(TokenType::Semicolon, "Expect ; after value.".into())
-            .unwrap();
+    fn expression_statement(&mut self) -> ParseResult {
+        let expression = self.expression()?;
+        let semicolon = self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
 
-        StmtExpression { value: expression }.into()
+        Ok(node(ExprStmt, vec![expression.into(), semicolon.into()]))
     }
 }
+
+fn node(kind: SyntaxKind, children: Vec<SyntaxElement>) -> SyntaxNode {
+    SyntaxNode::new(kind, children)
+}
+
+/** Lowers a node built by the parser, which only has `Error` nodes when parsing failed */
+fn lower(node: &SyntaxNode) -> AstNode {
+    node.to_ast()
+        .expect("statements without errors lower to an AstNode")
+}
@@ -0,0 +1,255 @@
+use std::fmt::{self, Write};
+
+use crate::ast::{AstNode, LiteralValue};
+use crate::scanner::TokenType;
+
+/* Binding strength of each level of the grammar, loosest first */
+const EXPRESSION: u8 = 0;
+const EQUALITY: u8 = 1;
+const COMPARISON: u8 = 2;
+const TERM: u8 = 3;
+const FACTOR: u8 = 4;
+const UNARY: u8 = 5;
+const CALL: u8 = 6;
+const PRIMARY: u8 = 7;
+
+/// Renders `node` as Lox source that parses back to the same tree; this is
+/// also what `AstNode`'s `Display` prints.
+///
+/// Parentheses are only added where a child binds looser than its parent,
+/// which can happen in trees that did not come from the parser, e.g. after
+/// the optimizer strips groupings.
+pub fn source(node: &AstNode) -> String {
+    node.to_string()
+}
+
+/** Renders a list of statements as source, one per line */
+pub fn program(statements: &[AstNode]) -> String {
+    let mut out = String::new();
+    for statement in statements {
+        let _ = writeln!(out, "{}", statement);
+    }
+    out
+}
+
+/// Renders `node` as a fully parenthesized prefix expression, e.g.
+/// `(* (group (+ 1 2)) 3)`, which shows the shape of the tree.
+pub fn lisp(node: &AstNode) -> String {
+    Lisp(node).to_string()
+}
+
+struct Lisp<'a>(&'a AstNode);
+
+impl fmt::Display for Lisp<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_lisp(f, self.0)
+    }
+}
+
+impl fmt::Display for AstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_source(f, self, EXPRESSION)
+    }
+}
+
+fn precedence(node: &AstNode) -> u8 {
+    match node {
+        AstNode::Binary { operator, .. } => match operator.ty {
+            TokenType::EqualEqual | TokenType::BangEqual => EQUALITY,
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => COMPARISON,
+            TokenType::Plus | TokenType::Minus => TERM,
+            _ => FACTOR,
+        },
+        AstNode::Unary { .. } => UNARY,
+        AstNode::Call { .. } | AstNode::Get { .. } => CALL,
+        AstNode::Expression { value } => precedence(value),
+        AstNode::Literal { value } => match value {
+            LiteralValue::Expression(node) => precedence(node),
+            /* Printed as a division */
+            LiteralValue::Number(number) if !number.is_finite() => FACTOR,
+            /* Printed with a leading `-` */
+            LiteralValue::Number(number) if number.is_sign_negative() => UNARY,
+            _ => PRIMARY,
+        },
+        _ => PRIMARY,
+    }
+}
+
+fn write_source(out: &mut dyn Write, node: &AstNode, min_precedence: u8) -> fmt::Result {
+    if precedence(node) < min_precedence {
+        out.write_char('(')?;
+        write_source(out, node, EXPRESSION)?;
+        out.write_char(')')?;
+        return Ok(());
+    }
+
+    match node {
+        AstNode::Binary { .. } | AstNode::Call { .. } | AstNode::Get { .. } => {
+            /* Walks down the chain while its links need no parentheses */
+            let mut links = vec![node];
+            let mut first = node.left().expect("a chain link has a left operand");
+            while let Some(left) = first.left() {
+                if precedence(first) < left_precedence(links[links.len() - 1]) {
+                    break;
+                }
+                links.push(first);
+                first = left;
+            }
+
+            write_source(out, first, left_precedence(links[links.len() - 1]))?;
+            for link in links.into_iter().rev() {
+                write_link(out, link)?;
+            }
+            Ok(())
+        }
+        AstNode::Unary { operator, right } => {
+            out.write_str(&operator.lexeme)?;
+            write_source(out, right, UNARY)
+        }
+        AstNode::Grouping { node } => {
+            out.write_char('(')?;
+            write_source(out, node, EXPRESSION)?;
+            out.write_char(')')?;
+            Ok(())
+        }
+        AstNode::Literal { value } => write_literal(out, value),
+        AstNode::Expression { value } => write_source(out, value, min_precedence),
+        AstNode::VariableExpression { value, .. } => write!(out, "{}", value),
+        AstNode::StmtExpression { value } => {
+            write_source(out, value, EXPRESSION)?;
+            out.write_char(';')
+        }
+        AstNode::StmtPrint { value } => {
+            out.write_str("print ")?;
+            write_source(out, value, EXPRESSION)?;
+            out.write_char(';')
+        }
+        AstNode::StmtVariable { name, initializer } => {
+            write!(out, "var {}", name)?;
+            if let Some(initializer) = initializer {
+                out.write_str(" = ")?;
+                write_source(out, initializer, EXPRESSION)?;
+            }
+            out.write_char(';')
+        }
+    }
+}
+
+/** The precedence the left operand, callee or object of `link` needs to go without parentheses */
+fn left_precedence(link: &AstNode) -> u8 {
+    match link {
+        AstNode::Binary { .. } => precedence(link),
+        _ => CALL,
+    }
+}
+
+/** Writes what follows the left operand of a chain link, like ` + b`, `(a, b)` or `.name` */
+fn write_link(out: &mut dyn Write, link: &AstNode) -> fmt::Result {
+    match link {
+        AstNode::Binary {
+            operator, right, ..
+        } => {
+            write!(out, " {} ", operator.lexeme)?;
+            /* Operators are left-associative, so an equal right operand needs parentheses */
+            write_source(out, right, precedence(link) + 1)
+        }
+        AstNode::Call { arguments, .. } => {
+            out.write_char('(')?;
+            for (i, argument) in arguments.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_source(out, argument, EXPRESSION)?;
+            }
+            out.write_char(')')
+        }
+        AstNode::Get { name, .. } => write!(out, ".{}", name.lexeme),
+        _ => Ok(()),
+    }
+}
+
+/// Values with no literal syntax, like natives, lists and maps, only appear
+/// in trees built by the host and are written in their display form.
+fn write_literal(out: &mut dyn Write, value: &LiteralValue) -> fmt::Result {
+    match value {
+        LiteralValue::Number(number) if number.is_nan() => write!(out, "0 / 0"),
+        LiteralValue::Number(number) if number.is_infinite() => {
+            write!(out, "{}1 / 0", if *number < 0.0 { "-" } else { "" })
+        }
+        LiteralValue::String(str) => write!(out, "\"{}\"", str),
+        LiteralValue::Expression(node) => write_source(out, node, EXPRESSION),
+        value => write!(out, "{}", value),
+    }
+}
+
+fn write_lisp(out: &mut dyn Write, node: &AstNode) -> fmt::Result {
+    match node {
+        AstNode::Binary { .. } | AstNode::Call { .. } | AstNode::Get { .. } => {
+            /* Opens every link of the chain before writing its innermost operand */
+            let mut links = vec![node];
+            let mut first = node.left().expect("a chain link has a left operand");
+            while let Some(left) = first.left() {
+                links.push(first);
+                first = left;
+            }
+
+            for link in &links {
+                match link {
+                    AstNode::Binary { operator, .. } => write!(out, "({} ", operator.lexeme)?,
+                    AstNode::Call { .. } => out.write_str("(call ")?,
+                    AstNode::Get { name, .. } => write!(out, "(get {} ", name.lexeme)?,
+                    _ => {}
+                }
+            }
+            write_lisp(out, first)?;
+            for link in links.into_iter().rev() {
+                match link {
+                    AstNode::Binary { right, .. } => {
+                        out.write_char(' ')?;
+                        write_lisp(out, right)?;
+                    }
+                    AstNode::Call { arguments, .. } => {
+                        for argument in arguments {
+                            out.write_char(' ')?;
+                            write_lisp(out, argument)?;
+                        }
+                    }
+                    _ => {}
+                }
+                out.write_char(')')?;
+            }
+            Ok(())
+        }
+        AstNode::Unary { operator, right } => parenthesize(out, &operator.lexeme, &[right]),
+        AstNode::Grouping { node } => parenthesize(out, "group", &[node]),
+        AstNode::Literal { value } => match value {
+            LiteralValue::String(str) => write!(out, "{:?}", str),
+            LiteralValue::Expression(node) => write_lisp(out, node),
+            value => write!(out, "{}", value),
+        },
+        AstNode::Expression { value } => write_lisp(out, value),
+        AstNode::VariableExpression { value, .. } => write!(out, "{}", value),
+        AstNode::StmtExpression { value } => parenthesize(out, "expr", &[value]),
+        AstNode::StmtPrint { value } => parenthesize(out, "print", &[value]),
+        AstNode::StmtVariable { name, initializer } => {
+            let name = format!("var {}", name);
+            match initializer {
+                Some(initializer) => parenthesize(out, &name, &[initializer]),
+                None => parenthesize(out, &name, &[]),
+            }
+        }
+    }
+}
+
+fn parenthesize(out: &mut dyn Write, name: &str, exprs: &[&AstNode]) -> fmt::Result {
+    write!(out, "({}", name)?;
+    for expr in exprs {
+        out.write_char(' ')?;
+        write_lisp(out, expr)?;
+    }
+    out.write_char(')')?;
+    Ok(())
+}
@@ -0,0 +1,487 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use rusting_interpreters::highlight;
+use rusting_interpreters::scanner::{Scanner, TokenType};
+use rusting_interpreters::{Interpreter, LiteralValue, LoxError};
+
+use crate::dump;
+use crate::session::{self, ExitRequest};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = ". ";
+
+pub fn run_prompt() -> io::Result<()> {
+    let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(ReplHelper::default()));
+    let history = history_path();
+    if let Some(history) = &history {
+        // A missing history file just means this is the first session
+        let _ = editor.load_history(history);
+    }
+
+    let exit = ExitRequest::default();
+    let mut interpreter = new_session(&exit);
+
+    let mut input = PendingInput::default();
+    loop {
+        let prompt = if input.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        if let Some(helper) = editor.helper_mut() {
+            helper.refresh(&interpreter);
+        }
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if input.is_empty() && line.trim_start().starts_with(':') {
+                    let _ = editor.add_history_entry(line.trim());
+                    meta_command(&mut interpreter, &exit, line.trim());
+                    if exit.code().is_some() {
+                        break;
+                    }
+                    continue;
+                }
+
+                let Some(source) = input.push(&line) else {
+                    continue;
+                };
+                if source.trim().is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(source.trim_end());
+                run(&mut interpreter, &exit, &source);
+                if exit.code().is_some() {
+                    break;
+                }
+            }
+            // Ctrl-C drops whatever has been typed so far but keeps the session
+            Err(ReadlineError::Interrupted) => input.cancel(),
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(io::Error::other(error)),
+        }
+    }
+
+    if let Some(history) = &history {
+        editor.save_history(history).map_err(io::Error::other)?;
+    }
+    if let Some(code) = exit.code() {
+        process::exit(code);
+    }
+
+    Ok(())
+}
+
+/** A fresh set of globals, where `exit()` ends the REPL once history is saved */
+fn new_session(exit: &ExitRequest) -> Interpreter {
+    let mut interpreter = session::interpreter(exit);
+    if io::stdout().is_terminal() {
+        interpreter.set_output(Coloured(io::stdout()));
+    }
+    interpreter
+}
+
+const HELP: &str = "\
+:env             list the global bindings
+:tokens <src>    show the tokens the scanner produces for <src>
+:ast <src>       show the syntax tree the parser produces for <src>
+:load <file>     run a file in this session
+:reset           start over with a fresh set of globals
+:time <expr>     evaluate <expr> and report how long it took
+:help            show this message";
+
+/** Runs a REPL command like `:env` or `:load file.lox` */
+fn meta_command(interpreter: &mut Interpreter, exit: &ExitRequest, line: &str) {
+    let (command, argument) = match line.split_once(char::is_whitespace) {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        ":env" => {
+            let mut bindings: Vec<_> = interpreter.environment().values.iter().collect();
+            bindings.sort_by(|(a, _), (b, _)| str::cmp(a, b));
+            for (name, value) in bindings {
+                println!("{} = {}", name, value);
+            }
+        }
+        ":tokens" => {
+            if let Err(error) = dump::tokens(argument) {
+                report(exit, &error);
+            }
+        }
+        ":ast" => {
+            if let Err(error) = dump::ast(argument) {
+                report(exit, &error);
+            }
+        }
+        ":load" if !argument.is_empty() => {
+            if let Err(error) = interpreter.eval_file(argument) {
+                report(exit, &error);
+            }
+        }
+        ":reset" => *interpreter = new_session(exit),
+        ":time" if !argument.is_empty() => {
+            let start = Instant::now();
+            let result = interpreter.eval_expr(argument);
+            let elapsed = start.elapsed();
+            match result {
+                Ok(value) => println!("{}", value),
+                Err(error) => report(exit, &error),
+            }
+            println!("took {:?}", elapsed);
+        }
+        ":help" => println!("{}", HELP),
+        ":load" | ":time" => eprintln!("{} needs an argument, see :help", command),
+        _ => eprintln!("Unknown command {}, see :help", command),
+    }
+}
+
+/** Echoes the value of a bare expression; anything else runs as statements */
+fn run(interpreter: &mut Interpreter, exit: &ExitRequest, source: &str) {
+    let result = match interpreter.eval_expr(source) {
+        Ok(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        Err(LoxError::Syntax(_)) => interpreter.eval_str(source),
+        Err(error) => Err(error),
+    };
+
+    if let Err(error) = result {
+        report(exit, &error);
+    }
+}
+
+/** Prints `error`, unless it is how `exit()` stopped the script */
+fn report(exit: &ExitRequest, error: &LoxError) {
+    if exit.code().is_none() {
+        eprintln!("{}", error);
+    }
+}
+
+/// Completes keywords and global names, and property names after a `.` on
+/// an instance. The names are refreshed from the session before each prompt.
+#[derive(Default)]
+struct ReplHelper {
+    keywords: Vec<String>,
+    globals: Vec<String>,
+    /** Property and method names of the instances bound to each global */
+    properties: HashMap<String, Vec<String>>,
+}
+
+impl ReplHelper {
+    fn refresh(&mut self, interpreter: &Interpreter) {
+        if self.keywords.is_empty() {
+            let scanner = Scanner::new(String::new());
+            self.keywords = scanner.keywords().map(String::from).collect();
+        }
+
+        self.globals.clear();
+        self.properties.clear();
+        for (name, value) in &interpreter.environment().values {
+            self.globals.push(name.to_string());
+            if let LiteralValue::Instance(instance) = value {
+                let names = instance.property_names().into_iter().map(String::from);
+                self.properties.insert(name.to_string(), names.collect());
+            }
+        }
+    }
+}
+
+/** Byte offset where the identifier ending at the end of `text` starts */
+fn identifier_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(text.len())
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = identifier_start(before);
+        let word = &before[start..];
+
+        let names: Vec<&String> = match before[..start].strip_suffix('.') {
+            Some(receiver) => {
+                let object = &receiver[identifier_start(receiver)..];
+                self.properties.get(object).into_iter().flatten().collect()
+            }
+            None => self.keywords.iter().chain(&self.globals).collect(),
+        };
+
+        let mut candidates: Vec<Pair> = names
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name.clone(),
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.display.cmp(&b.display));
+        candidates.dedup_by(|a, b| a.display == b.display);
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+/** Colours the input as it is typed; meta-commands are left alone */
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if line.starts_with(':') {
+            Cow::Borrowed(line)
+        } else {
+            Cow::Owned(highlight::to_ansi(line))
+        }
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rlox_history"))
+}
+
+/** The lines typed so far of an entry that spans several of them */
+#[derive(Default)]
+struct PendingInput(String);
+
+impl PendingInput {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /** Adds `line`, and takes every line so far once they form a complete entry */
+    fn push(&mut self, line: &str) -> Option<String> {
+        self.0.push_str(line);
+        self.0.push('\n');
+        is_complete(&self.0).then(|| std::mem::take(&mut self.0))
+    }
+
+    /** Drops the lines typed so far, as Ctrl-C does */
+    fn cancel(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Whether `source` can be run, or the user is still inside a block, call
+/// or string, or has ended the line with an operator that needs an operand.
+fn is_complete(source: &str) -> bool {
+    let mut scanner = Scanner::new(source.to_string());
+    let mut depth: i32 = 0;
+    let mut last = TokenType::Eof;
+    for token in scanner.scan_tokens() {
+        match token.ty {
+            TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+            _ => {}
+        }
+        if token.ty != TokenType::Eof {
+            last = token.ty;
+        }
+    }
+
+    let unterminated_string = scanner
+        .errors()
+        .iter()
+        .any(|error| error.message == "Unterminated string.");
+    let trailing_operator = matches!(
+        last,
+        TokenType::Comma
+            | TokenType::Dot
+            | TokenType::Minus
+            | TokenType::Plus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::Bang
+            | TokenType::BangEqual
+            | TokenType::Equal
+            | TokenType::EqualEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::And
+            | TokenType::Or
+    );
+    depth <= 0 && !unterminated_string && !trailing_operator
+}
+
+/** Shows program output in the REPL in a different colour from the input */
+struct Coloured<W: Write>(W);
+
+impl<W: Write> Write for Coloured<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(b"\x1b[36m")?;
+        self.0.write_all(buf)?;
+        self.0.write_all(b"\x1b[0m")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusting_interpreters::{lox_methods, Instance, IntoLox, LoxClass};
+
+    use super::*;
+
+    #[derive(LoxClass)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[lox_methods]
+    impl Point {
+        fn length(&self) -> f64 {
+            self.x.hypot(self.y)
+        }
+    }
+
+    /** The completions offered with the cursor at the end of `line`, and where they start */
+    fn complete(helper: &ReplHelper, line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        let (start, candidates) = helper
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap();
+        (
+            start,
+            candidates.into_iter().map(|pair| pair.display).collect(),
+        )
+    }
+
+    fn helper() -> ReplHelper {
+        let mut interpreter = session::interpreter(&ExitRequest::default());
+        interpreter.eval_str("var counter = 1;").unwrap();
+        interpreter.set_global("point", Instance::new(Point { x: 3.0, y: 4.0 }).into_lox());
+        let mut helper = ReplHelper::default();
+        helper.refresh(&interpreter);
+        helper
+    }
+
+    #[test]
+    fn input_is_complete_once_everything_opened_is_closed() {
+        for (source, complete) in [
+            ("print 1;", true),
+            ("1 + 2", true),
+            ("", true),
+            ("print (1,", false),
+            ("print (1,\n2);", true),
+            ("{ print 1;", false),
+            ("{ print 1; }", true),
+            ("print \"open", false),
+            ("print \"open\nstring\";", true),
+            ("print \"(\";", true),
+            ("print 1 +", false),
+            ("var a =", false),
+            ("a.", false),
+            ("a and", false),
+            ("print 1 + // comment", false),
+            ("print 1;)", true),
+        ] {
+            assert_eq!(is_complete(source), complete, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn ctrl_c_drops_the_lines_of_an_unfinished_entry() {
+        let mut input = PendingInput::default();
+        assert_eq!(input.push("print (1,"), None);
+        assert!(!input.is_empty());
+        input.cancel();
+        assert!(input.is_empty());
+        assert_eq!(input.push("print 2"), Some("print 2\n".to_string()));
+        assert_eq!(input.push("print 3 +"), None);
+        assert_eq!(input.push("4;"), Some("print 3 +\n4;\n".to_string()));
+    }
+
+    #[test]
+    fn identifiers_start_after_the_last_non_word_character() {
+        assert_eq!(identifier_start(""), 0);
+        assert_eq!(identifier_start("print fo"), 6);
+        assert_eq!(identifier_start("a.b_c1"), 2);
+        assert_eq!(identifier_start("é + ñu"), 5);
+        assert_eq!(identifier_start("f("), 2);
+    }
+
+    #[test]
+    fn completes_keywords_globals_and_natives() {
+        let helper = helper();
+        assert_eq!(complete(&helper, "pri"), (0, vec!["print".to_string()]));
+        assert_eq!(
+            complete(&helper, "1 + cou"),
+            (4, vec!["counter".to_string()])
+        );
+        assert_eq!(complete(&helper, "clo"), (0, vec!["clock".to_string()]));
+        assert_eq!(complete(&helper, "va"), (0, vec!["var".to_string()]));
+    }
+
+    #[test]
+    fn completes_properties_after_a_dot_on_an_instance() {
+        let helper = helper();
+        assert_eq!(
+            complete(&helper, "point."),
+            (
+                6,
+                vec!["length".to_string(), "x".to_string(), "y".to_string()]
+            )
+        );
+        assert_eq!(
+            complete(&helper, "print point.le"),
+            (12, vec!["length".to_string()])
+        );
+        assert_eq!(complete(&helper, "counter."), (8, vec![]));
+    }
+
+    #[test]
+    fn completes_an_empty_word_with_every_name_and_an_unknown_one_with_none() {
+        let helper = helper();
+        let (start, candidates) = complete(&helper, "1 + ");
+        assert_eq!(start, 4);
+        for name in ["print", "counter", "clock"] {
+            assert!(candidates.contains(&name.to_string()), "{}", name);
+        }
+        assert_eq!(complete(&helper, "zzz"), (0, vec![]));
+        assert_eq!(complete(&helper, "1."), (2, vec![]));
+    }
+}
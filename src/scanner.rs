@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
+use crate::error::SyntaxError;
 use crate::scanner::TokenType::{And, BangEqual, Class, Comma, Dot, Else, Eof, EqualEqual, False, For, Fun, GreaterEqual, Ident, If, LeftBrace, LeftParen, LessEqual, Minus, Nil, Number, Or, Plus, Print, Return, RightBrace, RightParen, Semicolon, Slash, Star, Super, This, True, Var, While};
 use crate::scanner::TokenValue::NumberLiteral;
 
@@ -10,6 +12,8 @@ pub struct Scanner {
     current: usize,
     line: usize,
     keywords: HashMap<String, TokenType>,
+    errors: Vec<SyntaxError>,
+    spans: Vec<Range<usize>>,
 }
 
 impl Scanner {
@@ -40,32 +44,54 @@ impl Scanner {
             current: 0,
             line: 1,
             keywords,
+            errors: vec![],
+            spans: vec![],
         }
     }
 
+    /** Every reserved word of the language */
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.keywords.keys().map(String::as_str)
+    }
+
     fn is_at_end(&self) -> bool {
-        &self.current >= &self.source.len()
+        self.current >= self.source.len()
     }
 
     pub fn scan_tokens(&mut self) -> &Vec<Token> {
-        while !&self.is_at_end() {
-            self.start = self.current.clone();
+        while !self.is_at_end() {
+            self.start = self.current;
             self.scan_token();
         }
 
         self.tokens
-            .push(Token::new(Eof, String::from(""), None, 0));
+            .push(Token::new(Eof, String::from(""), None, self.line));
+        self.spans.push(self.source.len()..self.source.len());
 
         &self.tokens
     }
 
+    /** Errors found by the last call to `scan_tokens` */
+    pub fn errors(&self) -> &Vec<SyntaxError> {
+        &self.errors
+    }
+
+    /** The byte range in the source of each token returned by `scan_tokens` */
+    pub fn spans(&self) -> &Vec<Range<usize>> {
+        &self.spans
+    }
+
+    fn error(&mut self, message: &str) {
+        self.errors.push(SyntaxError::new(self.line, message));
+    }
+
     fn scan_token(&mut self) {
         let c: char = self.advance();
         match c {
             '(' => self.add_token(LeftParen, None),
             ')' => self.add_token(RightParen, None),
-            '{' => self.add_token(RightBrace, None),
-            '}' => self.add_token(LeftBrace, None),
+            '{' => self.add_token(LeftBrace, None),
+            '}' => self.add_token(RightBrace, None),
             ',' => self.add_token(Comma, None),
             '.' => self.add_token(Dot, None),
             '-' => self.add_token(Minus, None),
@@ -92,7 +118,7 @@ impl Scanner {
                 let ty = if self.metch('=') {
                     LessEqual
                 } else {
-                    TokenType::Equal
+                    TokenType::Less
                 };
                 self.add_token(ty, None);
             }
@@ -100,7 +126,7 @@ impl Scanner {
                 let ty = if self.metch('=') {
                     GreaterEqual
                 } else {
-                    TokenType::Equal
+                    TokenType::Greater
                 };
                 self.add_token(ty, None);
             }
@@ -122,12 +148,12 @@ impl Scanner {
                 self.string();
             }
             any => {
-                if self.is_digit(any.clone()) {
+                if self.is_digit(any) {
                     self.number();
                 } else if self.is_alpha(any) {
                     self.identifier();
                 } else {
-                    crate::error(self.line.clone(), "Unexpected char");
+                    self.error("Unexpected character.");
                 }
             }
         }
@@ -139,7 +165,7 @@ impl Scanner {
             return '\0';
         }
 
-        return self.source.chars().nth(self.current.clone()).unwrap();
+        self.source[self.current..].chars().next().unwrap()
     }
 
     fn metch(&mut self, expected: char) -> bool {
@@ -147,11 +173,11 @@ impl Scanner {
             return false;
         }
 
-        if self.source.chars().nth(self.current.clone()).unwrap() != expected {
+        if self.peek() != expected {
             return false;
         }
 
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 
@@ -161,14 +187,16 @@ impl Scanner {
             ty,
             text.to_string(),
             value,
-            self.line.clone(),
+            self.line,
         ));
+        self.spans.push(self.start..self.current);
     }
 
     fn advance(&mut self) -> char {
-        let char_ = self.source.chars().nth(self.current.clone());
-        self.current += 1;
-        char_.unwrap()
+        /* `current` is a byte offset, so step over the whole character */
+        let char_ = self.source[self.current..].chars().next().unwrap();
+        self.current += char_.len_utf8();
+        char_
     }
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
@@ -179,18 +207,19 @@ impl Scanner {
         }
 
         if self.is_at_end() {
-            crate::error(self.line.clone(), "Unterminated string");
+            self.error("Unterminated string.");
+            return;
         }
 
         /*Closing " */
         self.advance();
 
-        let value = &self.source.as_str()[self.start.clone() + 1..self.current.clone() - 1];
+        let value = &self.source.as_str()[self.start + 1..self.current - 1];
         self.add_token(TokenType::String, Some(TokenValue::StringLiteral(value.to_string())));
     }
 
     fn is_digit(&self, c: char) -> bool {
-        c.clone() >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
 
     fn number(&mut self) {
@@ -206,22 +235,18 @@ impl Scanner {
             }
         }
 
-        let value = &self.source.as_str()[self.start.clone()..self.current.clone()];
+        let value = &self.source.as_str()[self.start..self.current];
 
         self.add_token(Number, Some(NumberLiteral(value.parse::<f64>().unwrap())));
     }
 
     fn peek_next(&self) -> char {
-        if self.current.clone() + 1 >= self.source.len() {
-            return '\0';
-        }
-
-        self.source.chars().nth(self.current.clone() + 1).unwrap()
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
     fn is_alpha(&self, c: char) -> bool {
-        (&c >= &'a' && &c <= &'z') ||
-            (&c >= &'A' && &c <= &'Z') ||
-            &c == &'_'
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     fn identifier(&mut self) {
@@ -229,13 +254,13 @@ impl Scanner {
             self.advance();
         }
 
-        let text = &self.source.as_str()[self.start.clone()..self.current.clone()];
+        let text = &self.source.as_str()[self.start..self.current];
         let ty = self.keywords.get(text).unwrap_or(&Ident);
 
         self.add_token(*ty, Some(TokenValue::Identifier(text.into())));
     }
     fn is_alphanumeric(&self, c: char) -> bool {
-        self.is_alpha(c.clone()) || self.is_digit(c)
+        self.is_alpha(c) || self.is_digit(c)
     }
 }
 
@@ -305,10 +330,10 @@ impl fmt::Display for TokenType {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
-    pub(crate) ty: TokenType,
-    pub(crate) lexeme: String,
-    pub(crate) literal: Option<TokenValue>,
-    pub(crate) line: usize,
+    pub ty: TokenType,
+    pub lexeme: String,
+    pub literal: Option<TokenValue>,
+    pub line: usize,
 }
 
 impl Token {
@@ -321,9 +346,12 @@ impl Token {
         }
     }
 
-    pub fn to_string(&self) {
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let ty = &self.ty;
         let lexeme = &self.lexeme;
-        format!("{ty} {lexeme}");
+        write!(f, "{ty} {lexeme}")
     }
 }
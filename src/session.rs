@@ -0,0 +1,35 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use rusting_interpreters::{Capability, Interpreter};
+
+/// Records a script's call to `exit(code)` in hosts that outlive their
+/// scripts, like the REPL and the watcher, where `Capability::Process`
+/// would end the host itself.
+#[derive(Clone, Default)]
+pub struct ExitRequest(Rc<Cell<Option<i32>>>);
+
+impl ExitRequest {
+    /** The code the script passed to `exit`, if it called it */
+    pub fn code(&self) -> Option<i32> {
+        self.0.get()
+    }
+}
+
+/// Builds an interpreter with every capability except `Process`. Its
+/// `exit(code)` stores the code in `exit` and stops the script with a
+/// runtime error, which the host should not report.
+pub fn interpreter(exit: &ExitRequest) -> Interpreter {
+    let granted: Vec<Capability> = Capability::ALL
+        .into_iter()
+        .filter(|&capability| capability != Capability::Process)
+        .collect();
+    let mut interpreter = Interpreter::builder().capabilities(&granted).build();
+
+    let requested = exit.0.clone();
+    interpreter.define_function("exit", move |code: i32| -> Result<(), String> {
+        requested.set(Some(code));
+        Err(format!("Script exited with code {}.", code))
+    });
+    interpreter
+}
@@ -0,0 +1,81 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+
+use crate::ast::LiteralValue;
+use crate::interpreter::Interpreter;
+use crate::native::{NativeFunction, TypedNative};
+
+/** A group of standard library features a host can grant to scripts */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /** The `print` statement; without it output is discarded */
+    Print,
+    /** `clock()`: seconds since the Unix epoch */
+    Clock,
+    /** `readFile(path)` and `writeFile(path, contents)` */
+    FileIo,
+    /** `getEnv(name)`: an environment variable, or nil */
+    Env,
+    /** `exit(code)`: ends the host process */
+    Process,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] = [
+        Capability::Print,
+        Capability::Clock,
+        Capability::FileIo,
+        Capability::Env,
+        Capability::Process,
+    ];
+}
+
+/** Defines the natives that make up `capability` as globals */
+pub(crate) fn install(interpreter: &mut Interpreter, capability: Capability) {
+    for native in natives(capability) {
+        let name = native.name.to_string();
+        interpreter.set_global(&name, LiteralValue::Native(native));
+    }
+}
+
+/// The name and arity of every native the standard library can define,
+/// sorted by name, for tools that list them without building an interpreter.
+pub fn native_names() -> Vec<(String, usize)> {
+    let mut names: Vec<(String, usize)> = Capability::ALL
+        .into_iter()
+        .flat_map(natives)
+        .map(|native| (native.name.to_string(), native.arity))
+        .collect();
+    names.sort();
+    names
+}
+
+/** The natives that make up `capability` */
+fn natives(capability: Capability) -> Vec<NativeFunction> {
+    match capability {
+        Capability::Print => vec![],
+        Capability::Clock => vec![(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs_f64())
+                .unwrap_or(0.0)
+        })
+        .into_native("clock")],
+        Capability::FileIo => vec![
+            (|path: String| {
+                fs::read_to_string(&path)
+                    .map_err(|error| format!("Could not read '{}': {}", path, error))
+            })
+            .into_native("readFile"),
+            (|path: String, contents: String| {
+                fs::write(&path, contents)
+                    .map_err(|error| format!("Could not write '{}': {}", path, error))
+            })
+            .into_native("writeFile"),
+        ],
+        Capability::Env => vec![(|name: String| env::var(name).ok()).into_native("getEnv")],
+        Capability::Process => {
+            vec![(|code: i32| -> () { process::exit(code) }).into_native("exit")]
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rusting_interpreters::IntoLox;
+
+use crate::session::{self, ExitRequest};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Runs the script at `path`, then polls its modification time and runs it
+/// again in a fresh interpreter whenever it changes. Never returns; stop it
+/// with Ctrl-C.
+pub fn run(path: &str, args: &[String]) -> ! {
+    let mut last_modified = None;
+    let mut missing = false;
+    loop {
+        match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) if last_modified != Some(modified) => {
+                last_modified = Some(modified);
+                missing = false;
+                run_once(path, args);
+            }
+            Ok(_) => {}
+            /* Editors often replace the file on save, so it can briefly be missing */
+            Err(error) if !missing => {
+                last_modified = None;
+                missing = true;
+                clear_screen();
+                eprintln!("Could not read script: {}\n\n[watching {}]", error, path);
+            }
+            Err(_) => {}
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn run_once(path: &str, args: &[String]) {
+    clear_screen();
+
+    /* `exit()` ends this run of the script, not the watcher */
+    let exit = ExitRequest::default();
+    let mut interpreter = session::interpreter(&exit);
+    interpreter.set_global("args", args.to_vec().into_lox());
+
+    let started = Instant::now();
+    let outcome = interpreter.eval_file(path);
+    let elapsed = started.elapsed();
+
+    match (outcome, exit.code()) {
+        (Err(_), Some(code)) => eprintln!(
+            "\n[exited with code {} after {:.2?}; watching {}]",
+            code, elapsed, path
+        ),
+        (Ok(()), _) => eprintln!("\n[finished in {:.2?}; watching {}]", elapsed, path),
+        (Err(error), None) => eprintln!("{}\n\n[failed; watching {}]", error, path),
+    }
+}
+
+fn clear_screen() {
+    print!("{}", CLEAR_SCREEN);
+    let _ = io::stdout().flush();
+}
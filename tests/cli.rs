@@ -0,0 +1,80 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn rlox(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn code(args: &[&str]) -> Option<i32> {
+    rlox(args, "").status.code()
+}
+
+#[test]
+fn scripts_receive_the_arguments_after_them() {
+    let output = rlox(&["-e", "print args;", "a", "-b"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[\"a\", \"-b\"]\n");
+
+    let output = rlox(&["-", "c"], "print args;");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[\"c\"]\n");
+}
+
+#[test]
+fn exits_with_a_sysexits_code_for_each_kind_of_failure() {
+    assert_eq!(code(&["-e", "print 1;"]), Some(0));
+    assert_eq!(code(&["--no-such-flag"]), Some(64));
+    assert_eq!(code(&["-e", "print ;"]), Some(65));
+    assert_eq!(code(&["-e", "print -\"a\";"]), Some(70));
+    assert_eq!(code(&["does/not/exist.lox"]), Some(74));
+    assert_eq!(code(&["-e", "exit(7);"]), Some(7));
+}
+
+#[test]
+fn dump_flags_work_before_the_run_command() {
+    let script = std::env::temp_dir().join(format!("rlox-dump-{}.lox", std::process::id()));
+    std::fs::write(&script, "print 1 + 2;").unwrap();
+    let path = script.to_str().unwrap();
+    let before = rlox(&["--dump-ast", "run", path], "");
+    let after = rlox(&["run", "--dump-ast", path], "");
+    std::fs::remove_file(&script).unwrap();
+
+    assert_eq!(before.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&before.stdout), "(print (+ 1 2))\n");
+    assert_eq!(before.stdout, after.stdout);
+}
+
+#[test]
+fn dumping_to_a_closed_pipe_is_an_io_error() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(["--dump-tokens", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("print 1;\n".repeat(100_000).as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(74));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
@@ -0,0 +1,9 @@
+use rusting_interpreters::{Interpreter, SharedBuffer};
+
+/** An interpreter with no natives whose output goes to the returned buffer */
+pub fn interpreter() -> (Interpreter, SharedBuffer) {
+    let output = SharedBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+    (interpreter, output)
+}
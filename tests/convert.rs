@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use rusting_interpreters::{FromLox, Interpreter, IntoLox, LiteralValue, LoxError};
+
+fn round_trip<T: IntoLox + FromLox + Clone>(value: T) -> T {
+    T::from_lox(&value.into_lox()).unwrap()
+}
+
+#[test]
+fn rust_values_convert_to_lox_and_back() {
+    assert!(round_trip(true));
+    assert_eq!(round_trip(2.5f64), 2.5);
+    assert_eq!(round_trip(-7i32), -7);
+    assert_eq!(round_trip("text".to_string()), "text");
+    assert_eq!(round_trip(None::<f64>), None);
+    assert_eq!(round_trip(vec![1u8, 2, 3]), vec![1, 2, 3]);
+
+    let map = HashMap::from([("one".to_string(), 1.0), ("two".to_string(), 2.0)]);
+    assert_eq!(round_trip(map.clone()), map);
+    assert_eq!(().into_lox(), LiteralValue::Nil);
+}
+
+#[test]
+fn conversion_errors_name_the_expected_type_and_position() {
+    assert_eq!(
+        f64::from_lox(&LiteralValue::Nil).unwrap_err(),
+        "Expected a number but got nil."
+    );
+    assert_eq!(
+        u8::from_lox(&LiteralValue::Number(1.5)).unwrap_err(),
+        "Expected an integer but got 1.5."
+    );
+    assert_eq!(
+        u8::from_lox(&LiteralValue::Number(300.0)).unwrap_err(),
+        "300 is out of range for u8."
+    );
+    assert_eq!(
+        Vec::<bool>::from_lox(&vec![true.into_lox(), 1.0.into_lox()].into_lox()).unwrap_err(),
+        "At index 1: Expected a boolean but got number."
+    );
+}
+
+#[test]
+fn integers_convert_up_to_their_bounds_and_no_further() {
+    let number = |value: f64| LiteralValue::Number(value);
+    let two_to = |power: i32| 2f64.powi(power);
+
+    assert_eq!(u8::from_lox(&number(255.0)), Ok(255));
+    assert!(u8::from_lox(&number(256.0)).is_err());
+    assert_eq!(i8::from_lox(&number(-128.0)), Ok(-128));
+    assert!(i8::from_lox(&number(128.0)).is_err());
+    assert!(u32::from_lox(&number(-1.0)).is_err());
+
+    assert_eq!(i64::from_lox(&number(-two_to(63))), Ok(i64::MIN));
+    assert_eq!(
+        i64::from_lox(&number(two_to(63) - 1024.0)),
+        Ok(i64::MAX - 1023)
+    );
+    assert!(i64::from_lox(&number(two_to(63))).is_err());
+    assert_eq!(
+        u64::from_lox(&number(two_to(64) - 2048.0)),
+        Ok(u64::MAX - 2047)
+    );
+    assert!(u64::from_lox(&number(two_to(64))).is_err());
+    assert!(usize::from_lox(&number(two_to(usize::BITS as i32))).is_err());
+}
+
+#[test]
+fn typed_natives_convert_arguments_and_results() {
+    let mut interpreter = Interpreter::new();
+    interpreter.define_function("repeat", |text: String, times: usize| text.repeat(times));
+    interpreter.define_function("half", |number: f64| -> Result<f64, String> {
+        if number < 0.0 {
+            return Err("Cannot halve a negative number.".to_string());
+        }
+        Ok(number / 2.0)
+    });
+
+    assert_eq!(
+        interpreter.eval_expr("repeat(\"ab\", 3)").unwrap(),
+        "ababab".into_lox()
+    );
+    assert_eq!(interpreter.eval_expr("half(5)").unwrap(), 2.5.into_lox());
+    for (source, message) in [
+        ("half(-1)", "Cannot halve a negative number."),
+        ("repeat(1, 2)", "Expected a string but got number."),
+    ] {
+        match interpreter.eval_expr(source) {
+            Err(LoxError::Runtime {
+                message: actual, ..
+            }) => {
+                assert!(actual.contains(message), "{}", actual)
+            }
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+}
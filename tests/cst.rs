@@ -0,0 +1,61 @@
+use proptest::prelude::*;
+
+use rusting_interpreters::cst::{self, SyntaxKind, TriviaKind};
+use rusting_interpreters::parser::Parser;
+use rusting_interpreters::scanner::Scanner;
+
+const SOURCE: &str = "\
+// Greeting
+var  greeting = \"hi\" ;  // trailing
+print greeting+ clock( 1 ,2 ).x;
+";
+
+#[test]
+fn trivia_is_split_at_the_end_of_each_line() {
+    let (tokens, errors) = cst::tokens(SOURCE);
+    assert!(errors.is_empty());
+
+    let var = &tokens[0];
+    assert_eq!(var.token.lexeme, "var");
+    assert_eq!(
+        var.leading
+            .iter()
+            .map(|trivia| trivia.kind)
+            .collect::<Vec<_>>(),
+        [TriviaKind::Comment, TriviaKind::Newline]
+    );
+
+    let semicolon = &tokens[4];
+    assert_eq!(semicolon.token.lexeme, ";");
+    assert_eq!(semicolon.trailing[1].text, "// trailing");
+    assert_eq!(tokens[5].leading[0].kind, TriviaKind::Newline);
+}
+
+#[test]
+fn the_ast_is_lowered_from_the_tree() {
+    let (tree, errors) = cst::parse(SOURCE);
+    assert!(errors.is_empty());
+
+    let mut scanner = Scanner::new(SOURCE.to_string());
+    let statements = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+    let lowered: Vec<_> = tree.nodes().filter_map(|node| node.to_ast()).collect();
+    assert_eq!(lowered, statements);
+}
+
+#[test]
+fn statements_that_do_not_parse_keep_their_tokens() {
+    let source = "print 1 +;\nvar a = 2;\n@ print \"open";
+    let (tree, errors) = cst::parse(source);
+
+    assert_eq!(errors.len(), 4);
+    assert_eq!(tree.nodes().next().unwrap().kind, SyntaxKind::Error);
+    assert_eq!(tree.to_string(), source);
+}
+
+proptest! {
+    #[test]
+    fn the_tree_prints_back_to_the_source(source in "(var|print|[a-z]{1,3}|[0-9.]{1,4}|\"[^\"]{0,4}\"?|//[^\n]{0,6}|[ \t\r\n]{1,3}|[(){};,.=!<>+*/-]|\\PC){0,40}") {
+        let (tree, _) = cst::parse(&source);
+        prop_assert_eq!(tree.to_string(), source);
+    }
+}
@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusting_interpreters::{
+    lox_methods, Instance, Interpreter, IntoLox, LiteralValue, LoxClass, LoxError,
+};
+
+#[derive(LoxClass, Clone)]
+struct Counter {
+    label: String,
+    count: f64,
+    #[lox(skip)]
+    secret: u32,
+}
+
+#[lox_methods]
+impl Counter {
+    fn add(&mut self, amount: f64) -> f64 {
+        self.count += amount;
+        self.count
+    }
+
+    fn reset(&mut self) {
+        self.count = 0.0;
+    }
+
+    #[lox(skip)]
+    fn secret(&self) -> u32 {
+        self.secret
+    }
+
+    fn into_count(self) -> f64 {
+        self.count
+    }
+
+    fn zero() -> f64 {
+        0.0
+    }
+}
+
+/** Calls back whatever function a script hands it */
+#[derive(LoxClass)]
+struct Relay {
+    calls: f64,
+}
+
+#[lox_methods]
+impl Relay {
+    fn ping(&mut self) -> f64 {
+        self.calls += 1.0;
+        self.calls
+    }
+
+    fn pass(&mut self, function: LiteralValue) -> Result<LiteralValue, String> {
+        match function {
+            LiteralValue::Native(function) => function.call(&[]),
+            value => Err(format!("Cannot call {}.", value.type_name())),
+        }
+    }
+}
+
+fn interpreter_with(counter: &Rc<RefCell<Counter>>) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("counter", Instance::from_rc(counter.clone()).into_lox());
+    interpreter
+}
+
+fn counter() -> Rc<RefCell<Counter>> {
+    Rc::new(RefCell::new(Counter {
+        label: "clicks".to_string(),
+        count: 1.0,
+        secret: 7,
+    }))
+}
+
+#[test]
+fn exposes_fields_and_reference_methods() {
+    let counter = counter();
+    let mut interpreter = interpreter_with(&counter);
+
+    assert_eq!(
+        interpreter.eval_expr("counter.label").unwrap(),
+        "clicks".into_lox()
+    );
+    assert_eq!(
+        interpreter.eval_expr("counter.add(2)").unwrap(),
+        3.0.into_lox()
+    );
+    interpreter.eval_expr("counter.reset()").unwrap();
+    assert_eq!(counter.borrow().count, 0.0);
+    assert_eq!(
+        Instance::from_rc(counter.clone()).property_names(),
+        vec!["label", "count", "add", "reset"]
+    );
+}
+
+#[test]
+fn skipped_and_by_value_members_stay_rust_only() {
+    let counter = counter();
+    let mut interpreter = interpreter_with(&counter);
+
+    for property in ["secret", "into_count", "zero"] {
+        let result = interpreter.eval_expr(&format!("counter.{}", property));
+        assert!(
+            matches!(result, Err(LoxError::Runtime { .. })),
+            "{}: {:?}",
+            property,
+            result
+        );
+    }
+    assert_eq!(counter.borrow().secret(), 7);
+    assert_eq!(Counter::zero(), 0.0);
+    assert_eq!(counter.borrow().clone().into_count(), 1.0);
+}
+
+#[test]
+fn reports_arguments_that_do_not_convert() {
+    let mut interpreter = interpreter_with(&counter());
+    match interpreter.eval_expr("counter.add(\"two\")") {
+        Err(LoxError::Runtime { message, .. }) => assert_eq!(
+            message,
+            "Argument 1 of 'add': Expected a number but got string."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_method_calling_back_into_its_own_instance_is_an_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("relay", Instance::new(Relay { calls: 0.0 }).into_lox());
+
+    match interpreter.eval_expr("relay.pass(relay.ping)") {
+        Err(LoxError::Runtime { message, .. }) => {
+            assert_eq!(message, "Relay instance is already in use.")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    assert_eq!(
+        interpreter.eval_expr("relay.ping()").unwrap(),
+        1.0.into_lox()
+    );
+}
@@ -0,0 +1,45 @@
+use rusting_interpreters::{LiteralValue, LoxError};
+
+mod common;
+
+#[test]
+fn globals_persist_between_evaluations() {
+    let (mut interpreter, output) = common::interpreter();
+
+    interpreter.eval_str("var greeting = \"hello\";").unwrap();
+    interpreter.set_global("name", LiteralValue::String("host".into()));
+    interpreter
+        .eval_str("print greeting + \" \" + name;")
+        .unwrap();
+
+    assert_eq!(output.contents(), "hello host\n");
+    assert_eq!(
+        interpreter.get_global("greeting"),
+        Some(LiteralValue::String("hello".into()))
+    );
+    assert_eq!(interpreter.get_global("missing"), None);
+    assert_eq!(
+        interpreter.eval_expr("1 + 2").unwrap(),
+        LiteralValue::Number(3.0)
+    );
+}
+
+#[test]
+fn a_variable_declared_without_a_value_is_nil() {
+    let (mut interpreter, output) = common::interpreter();
+
+    interpreter.eval_str("var b; print b;").unwrap();
+    assert_eq!(output.contents(), "nil\n");
+    assert_eq!(interpreter.get_global("b"), Some(LiteralValue::Nil));
+}
+
+#[test]
+fn reports_every_syntax_error_without_running_anything() {
+    let (mut interpreter, output) = common::interpreter();
+
+    match interpreter.eval_str("print 1;\nprint (;\nvar = 2;") {
+        Err(LoxError::Syntax(errors)) => assert_eq!(errors.len(), 2, "{:?}", errors),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+    assert_eq!(output.contents(), "");
+}
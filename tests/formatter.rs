@@ -0,0 +1,93 @@
+use rusting_interpreters::formatter::format;
+
+const MESSY: &str = "\
+// Leading comment
+var   a=1+2*-3 ;   // trailing
+
+
+class Foo<Bar{
+  init(x){this.x=x;}
+
+  // method
+    method( ){ if(!a and b){print \"yes\";}else{print\"no\";}
+  }
+}
+fun empty(){}
+";
+
+const FORMATTED: &str = "\
+// Leading comment
+var a = 1 + 2 * -3; // trailing
+
+class Foo < Bar {
+    init(x) {
+        this.x = x;
+    }
+
+    // method
+    method() {
+        if (!a and b) {
+            print \"yes\";
+        } else {
+            print \"no\";
+        }
+    }
+}
+fun empty() {}
+";
+
+#[test]
+fn formats_spacing_indentation_and_braces_and_keeps_comments() {
+    assert_eq!(format(MESSY).unwrap(), FORMATTED);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    assert_eq!(format(FORMATTED).unwrap(), FORMATTED);
+}
+
+#[test]
+fn long_lines_wrap_after_commas() {
+    let source =
+        "var long = call(argumentNumberOne, argumentNumberTwo, argumentNumberThree, argumentNumberFour);";
+    assert_eq!(
+        format(source).unwrap(),
+        "var long = call(argumentNumberOne, argumentNumberTwo, argumentNumberThree,\n    argumentNumberFour);\n"
+    );
+}
+
+#[test]
+fn line_width_counts_characters_not_bytes() {
+    let source = format!("print call(\"{}\", argument);", "é".repeat(50));
+    assert_eq!(format(&source).unwrap(), format!("{}\n", source));
+}
+
+#[test]
+fn a_semicolon_after_a_brace_stays_on_its_line() {
+    assert_eq!(
+        format("var x = {}; var y = 1;").unwrap(),
+        "var x = {};\nvar y = 1;\n"
+    );
+}
+
+#[test]
+fn scanner_errors_and_unmatched_brackets_are_reported() {
+    let messages = |source: &str| -> Vec<String> {
+        format(source)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.message)
+            .collect()
+    };
+    assert_eq!(messages("print \"open;"), ["Unterminated string."]);
+    assert_eq!(
+        messages("print (1;\n}"),
+        ["Unmatched closing bracket.", "Unclosed bracket."]
+    );
+}
+
+#[test]
+fn comments_after_a_multi_line_string_keep_their_place() {
+    let source = "var s = \"a\nb\"; // after\n\n// own line\nprint s;\n";
+    assert_eq!(format(source).unwrap(), source);
+}
@@ -0,0 +1,24 @@
+use rusting_interpreters::highlight::{highlight, to_html_fragment, Highlight};
+
+#[test]
+fn pieces_cover_the_whole_source() {
+    let source = "// note\nvar s = \"a<b\"; print s.len(1) @\n";
+    let pieces = highlight(source);
+
+    let text: String = pieces.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(text, source);
+    assert_eq!(pieces[0], (Highlight::Comment, "// note".to_string()));
+    assert!(pieces.contains(&(Highlight::Identifier, "s".to_string())));
+    assert!(pieces.contains(&(Highlight::Function, "len".to_string())));
+    assert!(pieces.contains(&(Highlight::Error, "@".to_string())));
+}
+
+#[test]
+fn html_is_escaped() {
+    assert_eq!(
+        to_html_fragment("print \"<&>\";"),
+        "<pre class=\"lox\"><code><span class=\"keyword\">print</span> \
+         <span class=\"string\">&quot;&lt;&amp;&gt;&quot;</span>\
+         <span class=\"punctuation\">;</span></code></pre>"
+    );
+}
@@ -0,0 +1,53 @@
+use std::sync::mpsc;
+use std::thread;
+
+use rusting_interpreters::{LiteralValue, LoxError};
+
+mod common;
+
+#[test]
+fn another_thread_can_stop_a_running_script() {
+    let (mut interpreter, output) = common::interpreter();
+
+    /* `pause()` hands control to the other thread until it has sent the interrupt */
+    let (paused, pause) = mpsc::channel();
+    let (resumed, resume) = mpsc::channel::<()>();
+    interpreter.define_native("pause", 0, move |_| {
+        paused.send(()).unwrap();
+        resume.recv().unwrap();
+        Ok(LiteralValue::Nil)
+    });
+    let handle = interpreter.interrupt_handle();
+    let stopper = thread::spawn(move || {
+        pause.recv().unwrap();
+        handle.interrupt();
+        resumed.send(()).unwrap();
+    });
+
+    let result = interpreter.eval_str("print 1; pause(); print 2;");
+    stopper.join().unwrap();
+    assert!(matches!(result, Err(LoxError::Interrupted)), "{:?}", result);
+    assert_eq!(output.take(), "1\n");
+
+    interpreter.eval_str("print 3;").unwrap();
+    assert_eq!(output.take(), "3\n");
+}
+
+#[test]
+fn an_interrupt_sent_while_idle_does_not_stop_the_next_script() {
+    let (mut interpreter, output) = common::interpreter();
+
+    interpreter.interrupt_handle().interrupt();
+    interpreter.eval_str("print 1;").unwrap();
+    assert_eq!(
+        interpreter.eval_expr("1 + 1").unwrap(),
+        LiteralValue::Number(2.0)
+    );
+
+    interpreter.interrupt_handle().interrupt();
+    assert_eq!(
+        interpreter.eval_expr("2 + 2").unwrap(),
+        LiteralValue::Number(4.0)
+    );
+    assert_eq!(output.contents(), "1\n");
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use rusting_interpreters::parser::MAX_NESTING;
+use rusting_interpreters::{cst, printer, IntoLox, Limit, Limits, LoxError};
+
+mod common;
+
+fn run(limits: Limits, source: &str) -> Result<String, LoxError> {
+    let (mut interpreter, output) = common::interpreter();
+    interpreter.set_limits(limits);
+    interpreter.set_global("a", "a".into_lox());
+    interpreter.eval_str(source).map(|()| output.contents())
+}
+
+fn exceeded(limits: Limits, source: &str) -> Limit {
+    match run(limits, source) {
+        Err(LoxError::LimitExceeded(limit)) => limit,
+        other => panic!("expected a limit to be exceeded, got {:?}", other),
+    }
+}
+
+fn too_deep(source: &str) {
+    match run(Limits::default(), source) {
+        Err(LoxError::Syntax(errors)) => assert_eq!(
+            errors[0].message, "Expression nests too deeply.",
+            "{:?}",
+            errors
+        ),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+/** `a` joined `count` times with `separator`, which nothing can fold away */
+fn chain(separator: &str, count: usize) -> String {
+    vec!["a"; count].join(separator)
+}
+
+#[test]
+fn each_limit_stops_the_script_it_applies_to() {
+    let steps = Limits {
+        max_steps: Some(10),
+        ..Limits::default()
+    };
+    assert_eq!(
+        exceeded(steps, &format!("{};", chain(" + ", 20))),
+        Limit::Steps
+    );
+
+    let depth = Limits {
+        max_call_depth: Some(20),
+        ..Limits::default()
+    };
+    let nested = format!("print {}a;", "-".repeat(60));
+    assert_eq!(exceeded(depth, &nested), Limit::CallDepth);
+
+    let heap = Limits {
+        max_heap_bytes: Some(64),
+        ..Limits::default()
+    };
+    assert_eq!(
+        exceeded(heap, &format!("{};", chain(" + ", 50))),
+        Limit::HeapBytes
+    );
+
+    let timeout = Limits {
+        timeout: Some(Duration::ZERO),
+        ..Limits::default()
+    };
+    assert_eq!(exceeded(timeout, &"a;".repeat(1000)), Limit::Timeout);
+}
+
+#[test]
+fn scripts_within_the_limits_run_and_limits_reset_between_runs() {
+    let limits = Limits {
+        max_steps: Some(1000),
+        max_call_depth: Some(100),
+        max_heap_bytes: Some(1000),
+        timeout: Some(Duration::from_secs(10)),
+    };
+    let (mut interpreter, output) = common::interpreter();
+    interpreter.set_limits(limits);
+    for _ in 0..3 {
+        interpreter
+            .eval_str("var b = \"b\" + \"c\"; print b + b;")
+            .unwrap();
+    }
+    assert_eq!(output.contents(), "bcbc\n".repeat(3));
+}
+
+#[test]
+fn deeply_nested_source_is_a_syntax_error_instead_of_a_crash() {
+    too_deep(&format!("print {}1;", "-".repeat(200_000)));
+    too_deep(&format!(
+        "print {}1{};",
+        "(".repeat(100_000),
+        ")".repeat(100_000)
+    ));
+    too_deep(&format!("print {}a;", "-".repeat(5000)));
+}
+
+#[test]
+fn long_chains_are_not_nesting() {
+    let sum = format!("print {};", vec!["1"; 1000].join(" + "));
+    assert_eq!(run(Limits::default(), &sum).unwrap(), "1000\n");
+
+    let source = format!("print {};", chain(" + ", 10_000));
+    assert_eq!(run(Limits::default(), &source).unwrap().len(), 10_001);
+    let (tree, errors) = cst::parse(&source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let ast = tree.nodes().next().unwrap().to_ast().unwrap();
+    assert_eq!(printer::source(&ast), source);
+
+    match run(
+        Limits::default(),
+        &format!("print a{};", "()".repeat(10_000)),
+    ) {
+        Err(LoxError::Runtime { message, .. }) => {
+            assert_eq!(message, "Can only call functions and classes.")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_pass_handles_the_deepest_allowed_nesting() {
+    let depth = MAX_NESTING - 2;
+    let source = format!(
+        "print {}a{};\nprint {}a;",
+        "(".repeat(depth),
+        ")".repeat(depth),
+        "!".repeat(depth)
+    );
+    let (tree, errors) = cst::parse(&source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tree.to_string(), source);
+    for statement in tree.nodes() {
+        let ast = statement.to_ast().unwrap();
+        assert!(!printer::source(&ast).is_empty());
+    }
+    assert_eq!(run(Limits::default(), &source).unwrap(), "a\ntrue\n");
+}
@@ -0,0 +1,65 @@
+use rusting_interpreters::lint::{lint, Config, Rule, Severity};
+
+fn rules(source: &str, config: &Config) -> Vec<(usize, Rule)> {
+    lint(source, config)
+        .unwrap()
+        .into_iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.rule))
+        .collect()
+}
+
+#[test]
+fn reports_each_rule_on_its_line() {
+    let source = "\
+var unused = 1;
+var x;
+print x == nil == false;
+print later;
+var later = 2;
+print later;
+var clock = 3;
+print clock;
+var y = 1;
+var y = y;
+print y;
+";
+    assert_eq!(
+        rules(source, &Config::default()),
+        vec![
+            (1, Rule::UnusedVariable),
+            (3, Rule::ChainedComparison),
+            (3, Rule::ReadBeforeAssignment),
+            (4, Rule::ReadBeforeAssignment),
+            (7, Rule::Shadowing),
+            (10, Rule::SelfAssignment),
+        ]
+    );
+}
+
+#[test]
+fn allow_comments_silence_a_rule_on_their_line_or_the_next() {
+    let source = "\
+var a = 1; // lint:allow(unused-variable)
+// lint:allow(unused-variable, shadowing)
+var clock = 2;
+var b = 3;
+";
+    assert_eq!(
+        rules(source, &Config::default()),
+        vec![(4, Rule::UnusedVariable)]
+    );
+}
+
+#[test]
+fn config_sets_severities_and_turns_rules_off() {
+    let config =
+        Config::parse("# project rules\nunused-variable = allow\nshadowing = error\n").unwrap();
+    assert_eq!(config.severity(Rule::Shadowing), Severity::Error);
+    assert_eq!(config.severity(Rule::SelfAssignment), Severity::Warning);
+
+    let diagnostics = lint("var clock = 1;", &config).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+
+    assert!(Config::parse("unused = error").is_err());
+}
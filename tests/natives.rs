@@ -0,0 +1,28 @@
+use rusting_interpreters::{Interpreter, LiteralValue, LoxError};
+
+fn runtime_error(interpreter: &mut Interpreter, source: &str) -> String {
+    match interpreter.eval_expr(source) {
+        Err(LoxError::Runtime { message, .. }) => message,
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn calls_host_functions_with_their_arguments() {
+    let mut interpreter = Interpreter::new();
+    interpreter.define_native("sum", 2, |arguments| match arguments {
+        [LiteralValue::Number(a), LiteralValue::Number(b)] => Ok(LiteralValue::Number(a + b)),
+        _ => Err("sum takes two numbers.".to_string()),
+    });
+
+    assert_eq!(
+        interpreter.eval_expr("sum(1, 2)").unwrap(),
+        LiteralValue::Number(3.0)
+    );
+    assert_eq!(
+        runtime_error(&mut interpreter, "sum(\"a\", 2)"),
+        "sum takes two numbers."
+    );
+    assert!(runtime_error(&mut interpreter, "sum(1)").contains("Expected 2 arguments"));
+    assert!(runtime_error(&mut interpreter, "\"sum\"(1, 2)").contains("call"));
+}
@@ -0,0 +1,42 @@
+use rusting_interpreters::ast::AstNode;
+use rusting_interpreters::optimizer::fold;
+use rusting_interpreters::parser::Parser;
+use rusting_interpreters::scanner::Scanner;
+use rusting_interpreters::{Interpreter, LiteralValue, LoxError};
+
+fn expression(source: &str) -> AstNode {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().clone();
+    Parser::new(tokens).parse_expression().unwrap()
+}
+
+#[test]
+fn folds_operators_on_literals_and_strips_groupings() {
+    assert_eq!(
+        fold(expression("(1 + 2) * -3 == -9")),
+        AstNode::Literal {
+            value: LiteralValue::True
+        }
+    );
+    assert_eq!(
+        fold(expression("\"a\" + \"b\"")),
+        AstNode::Literal {
+            value: LiteralValue::String("ab".into())
+        }
+    );
+}
+
+#[test]
+fn leaves_operations_that_fail_for_run_time() {
+    let folded = fold(expression("1 + (\"a\" - 1)"));
+    assert!(matches!(folded, AstNode::Binary { .. }), "{:?}", folded);
+    assert!(matches!(fold(expression("-\"a\"")), AstNode::Unary { .. }));
+
+    let error = Interpreter::new()
+        .eval_str("var a = 1;\nvar b = \"a\" - 1;")
+        .unwrap_err();
+    assert!(
+        matches!(error, LoxError::Runtime { line: 2, .. }),
+        "{:?}",
+        error
+    );
+}
@@ -0,0 +1,91 @@
+use std::cell::RefCell;
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+
+use rusting_interpreters::{Interpreter, LineSink, LoxError, SharedBuffer};
+
+mod common;
+
+#[test]
+fn shared_buffer_clones_see_the_same_output() {
+    let (mut interpreter, output) = common::interpreter();
+
+    interpreter.eval_str("print \"one\"; print 2;").unwrap();
+    assert_eq!(output.contents(), "one\n2\n");
+    assert_eq!(output.take(), "one\n2\n");
+    assert_eq!(output.contents(), "");
+
+    interpreter.eval_str("print nil;").unwrap();
+    assert_eq!(output.take(), "nil\n");
+}
+
+#[test]
+fn line_sink_emits_whole_lines_and_flushes_the_rest_on_drop() {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let seen = lines.clone();
+    let mut sink = LineSink::new(move |line| seen.borrow_mut().push(line.to_string()));
+
+    sink.write_all(b"first\nsec").unwrap();
+    assert_eq!(*lines.borrow(), ["first"]);
+    sink.write_all(b"ond\n\nthird").unwrap();
+    assert_eq!(*lines.borrow(), ["first", "second", ""]);
+
+    drop(sink);
+    assert_eq!(*lines.borrow(), ["first", "second", "", "third"]);
+}
+
+#[test]
+fn line_sink_receives_each_printed_line() {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let seen = lines.clone();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(LineSink::new(move |line| {
+        seen.borrow_mut().push(line.to_string())
+    }));
+
+    interpreter
+        .eval_str("print 1; print \"a\" + \"b\";")
+        .unwrap();
+    assert_eq!(*lines.borrow(), ["1", "ab"]);
+}
+
+#[test]
+fn output_is_flushed_even_when_the_script_fails() {
+    let output = SharedBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(BufWriter::new(output.clone()));
+
+    let result = interpreter.eval_str("print 1; print -\"a\";");
+    assert!(
+        matches!(result, Err(LoxError::Runtime { .. })),
+        "{:?}",
+        result
+    );
+    assert_eq!(output.take(), "1\n");
+}
+
+/** A sink whose reader has gone away */
+struct Closed;
+
+impl Write for Closed {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn failing_to_write_output_is_an_io_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Closed);
+
+    match interpreter.eval_str("print 1;") {
+        Err(error @ LoxError::Io(_)) => {
+            assert_eq!(error.to_string(), "Input/output error: broken pipe")
+        }
+        other => panic!("expected an I/O error, got {:?}", other),
+    }
+}
@@ -0,0 +1,191 @@
+use proptest::prelude::*;
+
+use rusting_interpreters::ast::AstNode;
+use rusting_interpreters::parser::Parser;
+use rusting_interpreters::printer;
+use rusting_interpreters::scanner::{Scanner, Token, TokenType, TokenValue};
+use rusting_interpreters::{LiteralValue, Symbol};
+
+/* Same levels as the grammar, loosest first */
+const EQUALITY: u8 = 1;
+const COMPARISON: u8 = 2;
+const TERM: u8 = 3;
+const FACTOR: u8 = 4;
+const UNARY: u8 = 5;
+const CALL: u8 = 6;
+
+const BINARY_OPERATORS: [(TokenType, &str, u8); 10] = [
+    (TokenType::EqualEqual, "==", EQUALITY),
+    (TokenType::BangEqual, "!=", EQUALITY),
+    (TokenType::Greater, ">", COMPARISON),
+    (TokenType::GreaterEqual, ">=", COMPARISON),
+    (TokenType::Less, "<", COMPARISON),
+    (TokenType::LessEqual, "<=", COMPARISON),
+    (TokenType::Plus, "+", TERM),
+    (TokenType::Minus, "-", TERM),
+    (TokenType::Star, "*", FACTOR),
+    (TokenType::Slash, "/", FACTOR),
+];
+
+/* Every generated statement is printed on line 1 */
+const LINE: usize = 1;
+
+fn token(ty: TokenType, lexeme: &str) -> Token {
+    Token::new(ty, lexeme.to_string(), None, LINE)
+}
+
+fn precedence(node: &AstNode) -> u8 {
+    match node {
+        AstNode::Binary { operator, .. } => {
+            BINARY_OPERATORS
+                .iter()
+                .find(|(ty, ..)| *ty == operator.ty)
+                .unwrap()
+                .2
+        }
+        AstNode::Unary { .. } => UNARY,
+        AstNode::Call { .. } | AstNode::Get { .. } => CALL,
+        _ => u8::MAX,
+    }
+}
+
+/** Wraps `node` in a grouping if the parser would need one to read it back */
+fn operand(node: AstNode, min_precedence: u8) -> Box<AstNode> {
+    if precedence(&node) < min_precedence {
+        Box::new(AstNode::Grouping {
+            node: Box::new(node),
+        })
+    } else {
+        Box::new(node)
+    }
+}
+
+fn identifier() -> impl Strategy<Value = String> {
+    "[a-zA-Z_][a-zA-Z0-9_]{0,6}".prop_filter("keywords are not identifiers", |name| {
+        !Scanner::new(String::new())
+            .keywords()
+            .any(|keyword| keyword == name)
+    })
+}
+
+fn literal() -> impl Strategy<Value = LiteralValue> {
+    prop_oneof![
+        Just(LiteralValue::True),
+        Just(LiteralValue::False),
+        Just(LiteralValue::Nil),
+        (0.0..1e12f64).prop_map(LiteralValue::Number),
+        (0u32..1000).prop_map(|number| LiteralValue::Number(number.into())),
+        "[^\"\n]{0,8}".prop_map(|str| LiteralValue::String(Symbol::intern(&str))),
+    ]
+}
+
+/** Trees shaped exactly like the ones the parser builds */
+fn expression() -> impl Strategy<Value = AstNode> {
+    let leaf = prop_oneof![
+        literal().prop_map(|value| AstNode::Literal { value }),
+        identifier().prop_map(|name| AstNode::VariableExpression {
+            value: Symbol::intern(&name),
+            line: LINE,
+        }),
+    ];
+
+    leaf.prop_recursive(6, 48, 4, |inner| {
+        prop_oneof![
+            (inner.clone(), 0..BINARY_OPERATORS.len(), inner.clone()).prop_map(
+                |(left, i, right)| {
+                    let (ty, lexeme, precedence) = BINARY_OPERATORS[i];
+                    AstNode::Binary {
+                        left: operand(left, precedence),
+                        operator: token(ty, lexeme),
+                        right: operand(right, precedence + 1),
+                    }
+                }
+            ),
+            (prop::bool::ANY, inner.clone()).prop_map(|(bang, right)| {
+                let operator = if bang {
+                    token(TokenType::Bang, "!")
+                } else {
+                    token(TokenType::Minus, "-")
+                };
+                AstNode::Unary {
+                    operator,
+                    right: operand(right, UNARY),
+                }
+            }),
+            inner.clone().prop_map(|node| AstNode::Grouping {
+                node: Box::new(node)
+            }),
+            (inner.clone(), prop::collection::vec(inner.clone(), 0..4)).prop_map(
+                |(callee, arguments)| AstNode::Call {
+                    callee: operand(callee, CALL),
+                    paren: token(TokenType::RightParen, ")"),
+                    arguments,
+                }
+            ),
+            (inner, identifier()).prop_map(|(object, name)| AstNode::Get {
+                object: operand(object, CALL),
+                name: Token::new(
+                    TokenType::Ident,
+                    name.clone(),
+                    Some(TokenValue::Identifier(name)),
+                    LINE,
+                ),
+            }),
+        ]
+    })
+}
+
+fn statement() -> impl Strategy<Value = AstNode> {
+    prop_oneof![
+        expression().prop_map(|value| AstNode::StmtPrint {
+            value: Box::new(value)
+        }),
+        expression().prop_map(|value| AstNode::StmtExpression {
+            value: Box::new(value)
+        }),
+        (identifier(), prop::option::of(expression())).prop_map(|(name, initializer)| {
+            AstNode::StmtVariable {
+                name: Symbol::intern(&name),
+                initializer: initializer.map(Box::new),
+            }
+        }),
+    ]
+}
+
+fn parse(source: &str) -> Vec<AstNode> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+    assert!(scanner.errors().is_empty(), "{:?}", scanner.errors());
+    Parser::new(tokens)
+        .parse()
+        .unwrap_or_else(|errors| panic!("{:?} in {}", errors, source))
+}
+
+proptest! {
+    #[test]
+    fn printed_source_parses_back_to_the_same_tree(statement in statement()) {
+        let source = printer::source(&statement);
+        prop_assert_eq!(parse(&source), vec![statement], "{}", source);
+    }
+}
+
+#[test]
+fn parentheses_are_added_where_the_tree_needs_them() {
+    let sum = AstNode::Binary {
+        left: Box::new(AstNode::Literal {
+            value: LiteralValue::Number(1.0),
+        }),
+        operator: token(TokenType::Plus, "+"),
+        right: Box::new(AstNode::Literal {
+            value: LiteralValue::Number(2.0),
+        }),
+    };
+    let product = AstNode::Binary {
+        left: Box::new(sum.clone()),
+        operator: token(TokenType::Star, "*"),
+        right: Box::new(sum),
+    };
+
+    assert_eq!(printer::source(&product), "(1 + 2) * (1 + 2)");
+    assert_eq!(printer::lisp(&product), "(* (+ 1 2) (+ 1 2))");
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/** Runs the REPL on `input` with its history kept under `home` */
+fn repl(home: &str, input: &str) -> Output {
+    let home = std::env::temp_dir().join(home);
+    fs::create_dir_all(&home).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .env("HOME", &home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn exit_ends_the_session_with_its_code_and_keeps_the_history() {
+    let home = format!("rlox-repl-exit-{}", std::process::id());
+    let output = repl(&home, "print 1;\nexit(3);\nprint 2;\n");
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    let home = std::env::temp_dir().join(home);
+    let history = fs::read_to_string(home.join(".rlox_history")).unwrap();
+    fs::remove_dir_all(home).unwrap();
+    assert!(history.ends_with("print 1;\nexit(3);\n"), "{}", history);
+}
+
+#[test]
+fn exit_from_a_loaded_file_ends_the_session() {
+    let home = format!("rlox-repl-load-{}", std::process::id());
+    let script = std::env::temp_dir().join(format!("{}.lox", home));
+    fs::write(&script, "print \"loaded\";\nexit(0);\n").unwrap();
+
+    let output = repl(&home, &format!(":load {}\nprint 2;\n", script.display()));
+    fs::remove_file(script).unwrap();
+    fs::remove_dir_all(std::env::temp_dir().join(home)).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "loaded\n");
+}
+
+#[test]
+fn bare_expressions_echo_their_value_and_statements_do_not() {
+    let home = format!("rlox-repl-echo-{}", std::process::id());
+    let output = repl(&home, "1 + 2\n1 + 2;\nvar a = \"x\";\na\nprint a;\n");
+    fs::remove_dir_all(std::env::temp_dir().join(home)).unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\nx\nx\n");
+}
+
+#[test]
+fn meta_commands_load_reset_and_reject_unknown_names() {
+    let home = format!("rlox-repl-meta-{}", std::process::id());
+    let script = std::env::temp_dir().join(format!("{}.lox", home));
+    fs::write(&script, "var loaded = 5;\n").unwrap();
+
+    let input = format!(
+        ":load {}\nloaded\n:nope\nloaded + 1\n:reset\nloaded\n",
+        script.display()
+    );
+    let output = repl(&home, &input);
+    fs::remove_file(script).unwrap();
+    fs::remove_dir_all(std::env::temp_dir().join(home)).unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5\n6\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Unknown command :nope, see :help\n[line 1] Runtime error: Undefined variable 'loaded'.\n"
+    );
+}
+
+#[test]
+fn a_statement_split_across_lines_runs_once() {
+    let home = format!("rlox-repl-lines-{}", std::process::id());
+    let output = repl(&home, "print (1 +\n2) *\n3;\nprint \"a\nb\";\n");
+    fs::remove_dir_all(std::env::temp_dir().join(home)).unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "9\na\nb\n");
+}
@@ -0,0 +1,65 @@
+use rusting_interpreters::{Capability, Interpreter, LiteralValue, SharedBuffer};
+
+fn globals(interpreter: &Interpreter) -> Vec<String> {
+    let mut names: Vec<String> = interpreter
+        .environment()
+        .values
+        .keys()
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn a_sandbox_with_no_capabilities_has_no_globals_and_discards_output() {
+    let output = SharedBuffer::new();
+    let mut interpreter = Interpreter::builder().output(output.clone()).build();
+    assert!(globals(&interpreter).is_empty());
+
+    interpreter.eval_str("print 1 + 2;").unwrap();
+    assert_eq!(output.contents(), "");
+    assert!(interpreter.eval_str("clock();").is_err());
+}
+
+#[test]
+fn each_capability_defines_only_its_own_natives() {
+    let cases: [(Capability, &[&str]); 5] = [
+        (Capability::Print, &[]),
+        (Capability::Clock, &["clock"]),
+        (Capability::FileIo, &["readFile", "writeFile"]),
+        (Capability::Env, &["getEnv"]),
+        (Capability::Process, &["exit"]),
+    ];
+    for (capability, natives) in cases {
+        let interpreter = Interpreter::builder().capability(capability).build();
+        assert_eq!(globals(&interpreter), natives, "{:?}", capability);
+    }
+
+    let all = Interpreter::builder()
+        .capabilities(&Capability::ALL)
+        .capability(Capability::Clock)
+        .build();
+    assert_eq!(
+        globals(&all),
+        ["clock", "exit", "getEnv", "readFile", "writeFile"]
+    );
+}
+
+#[test]
+fn granted_natives_run_and_print_goes_to_the_given_output() {
+    let output = SharedBuffer::new();
+    let mut interpreter = Interpreter::builder()
+        .capabilities(&[Capability::Print, Capability::Clock, Capability::Env])
+        .output(output.clone())
+        .build();
+
+    interpreter
+        .eval_str("print clock() > 0; print getEnv(\"RLOX_SANDBOX_UNSET\");")
+        .unwrap();
+    assert_eq!(output.contents(), "true\nnil\n");
+    assert_eq!(
+        interpreter.eval_expr("getEnv(\"PATH\") != nil").unwrap(),
+        LiteralValue::True
+    );
+}
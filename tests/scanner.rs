@@ -0,0 +1,25 @@
+use rusting_interpreters::scanner::{Scanner, TokenType, TokenValue};
+
+#[test]
+fn scans_non_ascii_strings_and_the_tokens_after_them() {
+    let mut scanner = Scanner::new("print \"héllo → wörld\" + \"¡\";".to_string());
+    let tokens = scanner.scan_tokens().clone();
+    assert!(scanner.errors().is_empty(), "{:?}", scanner.errors());
+
+    let types: Vec<TokenType> = tokens.iter().map(|token| token.ty).collect();
+    assert_eq!(
+        types,
+        vec![
+            TokenType::Print,
+            TokenType::String,
+            TokenType::Plus,
+            TokenType::String,
+            TokenType::Semicolon,
+            TokenType::Eof,
+        ]
+    );
+    assert_eq!(
+        tokens[1].literal,
+        Some(TokenValue::StringLiteral("héllo → wörld".to_string()))
+    );
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/** A running `rlox watch`, with its stdout and stderr merged line by line */
+struct Watcher {
+    child: Child,
+    script: PathBuf,
+    lines: Receiver<String>,
+}
+
+impl Watcher {
+    fn start(name: &str, source: &str) -> Watcher {
+        let script = std::env::temp_dir().join(format!("rlox-{}-{}.lox", name, std::process::id()));
+        fs::write(&script, source).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rlox"))
+            .arg("watch")
+            .arg(&script)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (sender, lines) = mpsc::channel();
+        forward(child.stdout.take().unwrap(), sender.clone());
+        forward(child.stderr.take().unwrap(), sender);
+        Watcher {
+            child,
+            script,
+            lines,
+        }
+    }
+
+    /** Waits for a line containing `text` and returns it */
+    fn wait_for(&self, text: &str) -> String {
+        loop {
+            match self.lines.recv_timeout(Duration::from_secs(10)) {
+                Ok(line) if line.contains(text) => return line,
+                Ok(_) => {}
+                Err(error) => panic!("no line containing {:?}: {}", text, error),
+            }
+        }
+    }
+
+    /** Replaces the script, making sure its modification time changes */
+    fn rewrite(&self, source: &str) {
+        fs::write(&self.script, source).unwrap();
+        let file = fs::File::options().write(true).open(&self.script).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+    }
+
+    fn is_running(&mut self) -> bool {
+        self.child.try_wait().unwrap().is_none()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_file(&self.script);
+    }
+}
+
+fn forward(stream: impl Read + Send + 'static, sender: mpsc::Sender<String>) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[test]
+fn exit_ends_the_run_but_not_the_watcher() {
+    let mut watcher = Watcher::start("watch-exit", "print \"hi\";\nexit(2);\n");
+
+    let status = watcher.wait_for("[exited with code ");
+    assert!(
+        status.starts_with("[exited with code 2 after "),
+        "{}",
+        status
+    );
+    assert!(watcher.is_running());
+}
+
+#[test]
+fn saving_the_script_runs_it_again() {
+    let mut watcher = Watcher::start("watch-edit", "print \"first\";\n");
+    watcher.wait_for("first");
+    watcher.wait_for("[finished in ");
+
+    watcher.rewrite("print \"second\";\n");
+    watcher.wait_for("second");
+    watcher.wait_for("[finished in ");
+
+    watcher.rewrite("print ;\n");
+    watcher.wait_for("Expect expression.");
+    watcher.wait_for("[failed; watching ");
+    assert!(watcher.is_running());
+
+    watcher.rewrite("print \"third\";\n");
+    watcher.wait_for("third");
+}